@@ -0,0 +1,394 @@
+//! An assembler for the syntax [`disasm`](crate::disasm) produces, for writing test ROMs.
+//!
+//! Every line holds an optional `label:` followed by an instruction or directive, and anything
+//! after a `;` is a comment:
+//!
+//! ```text
+//! SPEED = 2                   ; constants are defined with = or EQU
+//!
+//! loop:
+//!     LD I, sprite
+//!     DRW V0, V1, 2
+//!     ADD V0, SPEED
+//!     JP loop
+//! sprite:
+//!     DB 0xFF, 0b10000001     ; DB lists bytes and DW big endian words
+//!     INCLUDE "more.asm"      ; paths are relative to the including file
+//! ```
+//!
+//! Mnemonics and registers are case insensitive, labels and constants aren't. Operands are
+//! decimal, `0x` hex or `0b` binary numbers, labels or constants, added or subtracted with `+`
+//! and `-`. The program is assembled to run from 0x200.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::disasm::START_ADDRESS;
+use crate::instruction::Instruction;
+
+/// Deepest nesting of `INCLUDE`s and of constants defined by other constants, which stops files
+/// including themselves and constants defined in terms of themselves.
+const MAX_DEPTH: usize = 16;
+
+const MNEMONICS: [&str; 32] = [
+    "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "AUDIO", "SCD", "SCU", "PLANE", "SYS", "JP", "CALL", "SE",
+    "SNE", "SAVE", "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SUBN", "SHR", "SHL", "RND", "DRW", "SKP",
+    "SKNP", "PITCH",
+];
+
+/// A problem with the source, at a line of a file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+/// Assembles source code, resolving `INCLUDE`s relative to the working directory.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut assembler = Assembler::new();
+    assembler.parse(source, "<input>", Path::new(""), 0)?;
+    assembler.emit()
+}
+
+/// Assembles the source file at `path`.
+pub fn assemble_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, AsmError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| AsmError {
+        file: path.display().to_string(),
+        line: 0,
+        message: format!("could not read file: {}", e),
+    })?;
+
+    let mut assembler = Assembler::new();
+    assembler.parse(&source, &path.display().to_string(), path.parent().unwrap_or_else(|| Path::new("")), 0)?;
+    assembler.emit()
+}
+
+enum Statement {
+    Instruction { mnemonic: String, operands: Vec<String> },
+    Bytes(Vec<String>),
+    Words(Vec<String>),
+}
+
+struct Line {
+    file: String,
+    number: usize,
+    statement: Statement,
+}
+
+enum Symbol {
+    Label(u16),
+    Constant(String),
+}
+
+/// An operand, classified by its syntax.
+enum Operand<'a> {
+    V(u8),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    Long(&'a str),
+    Value(&'a str),
+}
+
+struct Assembler {
+    lines: Vec<Line>,
+    symbols: HashMap<String, Symbol>,
+    address: usize,
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler {
+            lines: Vec::new(),
+            symbols: HashMap::new(),
+            address: START_ADDRESS as usize,
+        }
+    }
+
+    /// First pass, collecting the statements and giving every label its address.
+    fn parse(&mut self, source: &str, file: &str, dir: &Path, depth: usize) -> Result<(), AsmError> {
+        for (index, text) in source.lines().enumerate() {
+            let error = |message: String| AsmError { file: file.to_string(), line: index + 1, message };
+
+            let mut text = text.split(';').next().unwrap().trim();
+            if let Some((label, rest)) = text.split_once(':') {
+                if is_identifier(label.trim()) {
+                    let label = label.trim();
+                    if self.symbols.contains_key(label) {
+                        return Err(error(format!("'{}' is already defined", label)));
+                    }
+                    self.symbols.insert(label.to_string(), Symbol::Label(self.address as u16));
+                    text = rest.trim();
+                }
+            }
+            if text.is_empty() {
+                continue;
+            }
+
+            let (word, args) = match text.split_once(char::is_whitespace) {
+                Some((word, args)) => (word, args.trim()),
+                None => (text, ""),
+            };
+
+            let constant = match text.split_once('=') {
+                Some((name, value)) if is_identifier(name.trim()) => Some((name.trim(), value.trim())),
+                _ => match args.split_once(char::is_whitespace) {
+                    Some((equ, value)) if equ.eq_ignore_ascii_case("EQU") => Some((word, value.trim())),
+                    _ => None,
+                },
+            };
+            if let Some((name, value)) = constant {
+                if self.symbols.contains_key(name) {
+                    return Err(error(format!("'{}' is already defined", name)));
+                }
+                self.symbols.insert(name.to_string(), Symbol::Constant(value.to_string()));
+                continue;
+            }
+
+            let operands: Vec<String> = if args.is_empty() {
+                Vec::new()
+            } else {
+                args.split(',').map(|operand| operand.trim().to_string()).collect()
+            };
+            let mnemonic = word.to_uppercase();
+            let (statement, size) = match mnemonic.as_str() {
+                "DB" => (Statement::Bytes(operands.clone()), operands.len()),
+                "DW" => (Statement::Words(operands.clone()), 2 * operands.len()),
+                "INCLUDE" => {
+                    if depth == MAX_DEPTH {
+                        return Err(error(String::from("includes are nested too deeply")));
+                    }
+                    let path = dir.join(args.trim_matches('"'));
+                    let source = fs::read_to_string(&path)
+                        .map_err(|e| error(format!("could not include '{}': {}", path.display(), e)))?;
+                    self.parse(&source, &path.display().to_string(), path.parent().unwrap_or(dir), depth + 1)?;
+                    continue;
+                }
+                _ => {
+                    let long = mnemonic == "LD" && operands.len() == 2 && operands[1].to_uppercase().starts_with("LONG ");
+                    (Statement::Instruction { mnemonic, operands }, if long { 4 } else { 2 })
+                }
+            };
+
+            self.address += size;
+            if self.address > 0x10000 {
+                return Err(error(String::from("the program doesn't fit in memory")));
+            }
+            self.lines.push(Line { file: file.to_string(), number: index + 1, statement });
+        }
+        Ok(())
+    }
+
+    /// Second pass, encoding the statements now that every label is known.
+    fn emit(&self) -> Result<Vec<u8>, AsmError> {
+        let mut program = Vec::with_capacity(self.address - START_ADDRESS as usize);
+        for line in &self.lines {
+            let error = |message: String| AsmError { file: line.file.clone(), line: line.number, message };
+            match &line.statement {
+                Statement::Bytes(values) => {
+                    for value in values {
+                        program.push(self.value(value, 0xFF, 0).map_err(error)? as u8);
+                    }
+                }
+                Statement::Words(values) => {
+                    for value in values {
+                        program.extend_from_slice(&self.value(value, 0xFFFF, 0).map_err(error)?.to_be_bytes());
+                    }
+                }
+                Statement::Instruction { mnemonic, operands } => {
+                    let (instruction, long) = self.instruction(mnemonic, operands).map_err(error)?;
+                    program.extend_from_slice(&instruction.encode().to_be_bytes());
+                    if let Some(address) = long {
+                        program.extend_from_slice(&address.to_be_bytes());
+                    }
+                }
+            }
+        }
+        Ok(program)
+    }
+
+    /// Encodes an instruction, also returning the address following `LD I, LONG`.
+    fn instruction(&self, mnemonic: &str, operands: &[String]) -> Result<(Instruction, Option<u16>), String> {
+        use Instruction::*;
+        use Operand::*;
+
+        let parsed: Vec<Operand> = operands.iter().map(|operand| classify(operand)).collect();
+        let address = |value: &str| self.value(value, 0xFFF, 0);
+        // Bytes may also be negative, as in ADD V0, -1
+        let byte = |value: &str| match self.evaluate(value, 0)? {
+            value @ -0x80..=0xFF => Ok(value as u8),
+            _ => Err(format!("{} is out of range, expected a byte", value)),
+        };
+        let nibble = |value: &str| self.value(value, 0xF, 0).map(|value| value as u8);
+
+        let instruction = match (mnemonic, parsed.as_slice()) {
+            ("CLS", []) => Cls,
+            ("RET", []) => Ret,
+            ("SCR", []) => Scr,
+            ("SCL", []) => Scl,
+            ("EXIT", []) => Exit,
+            ("LOW", []) => Low,
+            ("HIGH", []) => High,
+            ("AUDIO", []) => Audio,
+            ("SCD", [Value(n)]) => Scd(nibble(n)?),
+            ("SCU", [Value(n)]) => Scu(nibble(n)?),
+            ("PLANE", [Value(n)]) => Plane(self.value(n, 0x3, 0)? as u8),
+            ("SYS", [Value(addr)]) => Sys(address(addr)?),
+            ("JP", [Value(addr)]) => Jp(address(addr)?),
+            ("JP", [V(0), Value(addr)]) => JpV0(address(addr)?),
+            ("CALL", [Value(addr)]) => Call(address(addr)?),
+            ("SE", [V(x), V(y)]) => SeVxVy { x: *x, y: *y },
+            ("SE", [V(x), Value(nn)]) => SeVxByte { x: *x, nn: byte(nn)? },
+            ("SNE", [V(x), V(y)]) => SneVxVy { x: *x, y: *y },
+            ("SNE", [V(x), Value(nn)]) => SneVxByte { x: *x, nn: byte(nn)? },
+            ("SAVE", [V(x), V(y)]) => SaveRange { x: *x, y: *y },
+            ("LOAD", [V(x), V(y)]) => LoadRange { x: *x, y: *y },
+            ("LD", [V(x), V(y)]) => LdVxVy { x: *x, y: *y },
+            ("LD", [V(x), Value(nn)]) => LdVxByte { x: *x, nn: byte(nn)? },
+            ("LD", [I, Value(addr)]) => LdI(address(addr)?),
+            ("LD", [I, Long(addr)]) => return Ok((LdILong, Some(self.value(addr, 0xFFFF, 0)?))),
+            ("LD", [V(x), Dt]) => LdVxDt(*x),
+            ("LD", [V(x), K]) => LdVxK(*x),
+            ("LD", [Dt, V(x)]) => LdDtVx(*x),
+            ("LD", [St, V(x)]) => LdStVx(*x),
+            ("LD", [F, V(x)]) => LdFVx(*x),
+            ("LD", [Hf, V(x)]) => LdHfVx(*x),
+            ("LD", [B, V(x)]) => LdBVx(*x),
+            ("LD", [IndirectI, V(x)]) => LdIVx(*x),
+            ("LD", [V(x), IndirectI]) => LdVxI(*x),
+            ("LD", [R, V(x)]) => LdRVx(*x),
+            ("LD", [V(x), R]) => LdVxR(*x),
+            ("ADD", [V(x), V(y)]) => AddVxVy { x: *x, y: *y },
+            ("ADD", [V(x), Value(nn)]) => AddVxByte { x: *x, nn: byte(nn)? },
+            ("ADD", [I, V(x)]) => AddIVx(*x),
+            ("OR", [V(x), V(y)]) => Or { x: *x, y: *y },
+            ("AND", [V(x), V(y)]) => And { x: *x, y: *y },
+            ("XOR", [V(x), V(y)]) => Xor { x: *x, y: *y },
+            ("SUB", [V(x), V(y)]) => Sub { x: *x, y: *y },
+            ("SUBN", [V(x), V(y)]) => Subn { x: *x, y: *y },
+            ("SHR", [V(x)]) => Shr { x: *x, y: *x },
+            ("SHR", [V(x), V(y)]) => Shr { x: *x, y: *y },
+            ("SHL", [V(x)]) => Shl { x: *x, y: *x },
+            ("SHL", [V(x), V(y)]) => Shl { x: *x, y: *y },
+            ("RND", [V(x), Value(nn)]) => Rnd { x: *x, nn: byte(nn)? },
+            ("DRW", [V(x), V(y), Value(n)]) => Drw { x: *x, y: *y, n: nibble(n)? },
+            ("SKP", [V(x)]) => Skp(*x),
+            ("SKNP", [V(x)]) => Sknp(*x),
+            ("PITCH", [V(x)]) => Pitch(*x),
+            _ if !MNEMONICS.contains(&mnemonic) => return Err(format!("unknown instruction '{}'", mnemonic)),
+            _ => return Err(format!("invalid operands for {}: {}", mnemonic, operands.join(", "))),
+        };
+        Ok((instruction, None))
+    }
+
+    /// Evaluates an expression, which has to be between `0` and `max`.
+    fn value(&self, expression: &str, max: u16, depth: usize) -> Result<u16, String> {
+        let value = self.evaluate(expression, depth)?;
+        if value < 0 || value > max as i64 {
+            return Err(format!("{} is out of range, expected a value up to {:#X}", expression, max));
+        }
+        Ok(value as u16)
+    }
+
+    fn evaluate(&self, expression: &str, depth: usize) -> Result<i64, String> {
+        if depth == MAX_DEPTH {
+            return Err(format!("'{}' is defined in terms of itself", expression));
+        }
+
+        let mut total = 0;
+        let mut sign = 1;
+        let mut rest = expression.trim();
+        if rest.is_empty() {
+            return Err(String::from("missing value"));
+        }
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('-') {
+                sign = -sign;
+                rest = after.trim_start();
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix('+') {
+                rest = after.trim_start();
+                continue;
+            }
+
+            let end = rest.find(&['+', '-'][..]).unwrap_or(rest.len());
+            let term = rest[..end].trim();
+            let value = match parse_number(term) {
+                Some(value) => value,
+                None => match self.symbols.get(term) {
+                    Some(Symbol::Label(address)) => *address as i64,
+                    Some(Symbol::Constant(value)) => self.evaluate(value, depth + 1)?,
+                    None if is_identifier(term) => return Err(format!("undefined label or constant '{}'", term)),
+                    None => return Err(format!("invalid value '{}'", term)),
+                },
+            };
+            total += sign * value;
+            sign = 1;
+            rest = rest[end..].trim_start();
+        }
+        Ok(total)
+    }
+}
+
+fn classify(operand: &str) -> Operand<'_> {
+    let upper = operand.to_uppercase();
+    match upper.as_str() {
+        "I" => return Operand::I,
+        "[I]" => return Operand::IndirectI,
+        "DT" => return Operand::Dt,
+        "ST" => return Operand::St,
+        "K" => return Operand::K,
+        "F" => return Operand::F,
+        "HF" => return Operand::Hf,
+        "B" => return Operand::B,
+        "R" => return Operand::R,
+        _ => {}
+    }
+    if upper.len() == 2 && upper.starts_with('V') {
+        if let Some(x) = upper[1..].chars().next().and_then(|c| c.to_digit(16)) {
+            return Operand::V(x as u8);
+        }
+    }
+    if upper.starts_with("LONG ") {
+        return Operand::Long(operand[5..].trim());
+    }
+    Operand::Value(operand)
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let lower = text.to_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
@@ -0,0 +1,187 @@
+//! Sound output, from the sinks the [`CPU`](crate::CPU) reports the buzzer to down to the
+//! samples frontends play.
+
+/// Plays the CHIP-8 buzzer, which sounds while the sound timer is non-zero.
+pub trait Audio {
+    /// Called by the [`CPU`](crate::CPU) whenever the buzzer starts or stops.
+    fn set_tone(&mut self, playing: bool);
+
+    /// Called when an XO-CHIP program loads a new audio pattern (`F002`) or pitch (`FX3A`). The
+    /// pattern is 128 1-bit samples played at [`pattern_rate`]`(pitch)` samples per second.
+    ///
+    /// Programs that never call this expect a plain buzzer.
+    fn set_pattern(&mut self, _pattern: &[u8; 16], _pitch: u8) {}
+}
+
+/// The pitch XO-CHIP programs start with, playing patterns at 4000 samples per second.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Number of pattern samples played per second for an XO-CHIP pitch.
+pub fn pattern_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+}
+
+/// An [`Audio`] sink that discards everything.
+#[derive(Default)]
+pub struct NullAudio;
+
+impl Audio for NullAudio {
+    fn set_tone(&mut self, _playing: bool) {}
+}
+
+/// An [`Audio`] sink that keeps every buzzer change, for tests and headless runs.
+#[derive(Default)]
+pub struct RecordingAudio {
+    changes: Vec<bool>,
+    pattern: Option<([u8; 16], u8)>,
+}
+
+impl RecordingAudio {
+    pub fn new() -> RecordingAudio {
+        RecordingAudio::default()
+    }
+
+    /// Every change of the buzzer in order, `true` when it started playing.
+    pub fn changes(&self) -> &[bool] {
+        &self.changes
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.changes.last().copied().unwrap_or(false)
+    }
+
+    /// The last XO-CHIP audio pattern and pitch, if the program set one.
+    pub fn pattern(&self) -> Option<([u8; 16], u8)> {
+        self.pattern
+    }
+}
+
+impl Audio for RecordingAudio {
+    fn set_tone(&mut self, playing: bool) {
+        self.changes.push(playing);
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8; 16], pitch: u8) {
+        self.pattern = Some((*pattern, pitch));
+    }
+}
+
+/// Generates the samples of a square wave, for [`Audio`] implementations backed by a sound card.
+pub struct SquareWave {
+    frequency: f32,
+    sample_rate: f32,
+    phase: f32,
+}
+
+impl SquareWave {
+    pub fn new(frequency: f32, sample_rate: u32) -> SquareWave {
+        SquareWave {
+            frequency,
+            sample_rate: sample_rate as f32,
+            phase: 0.0,
+        }
+    }
+
+    /// Fills `samples` with the wave at the given volume, or with silence if `playing` is false.
+    pub fn fill(&mut self, samples: &mut [f32], playing: bool, volume: f32) {
+        for sample in samples.iter_mut() {
+            *sample = if !playing {
+                0.0
+            } else if self.phase < 0.5 {
+                volume
+            } else {
+                -volume
+            };
+            self.phase = (self.phase + self.frequency / self.sample_rate).fract();
+        }
+    }
+}
+
+/// Generates the samples of an XO-CHIP audio pattern, see [`Audio::set_pattern`].
+pub struct PatternWave {
+    pattern: [u8; 16],
+    step: f32,
+    sample_rate: f32,
+    position: f32,
+}
+
+impl PatternWave {
+    pub fn new(pattern: [u8; 16], pitch: u8, sample_rate: u32) -> PatternWave {
+        PatternWave {
+            pattern,
+            step: pattern_rate(pitch) / sample_rate as f32,
+            sample_rate: sample_rate as f32,
+            position: 0.0,
+        }
+    }
+
+    pub fn set_pattern(&mut self, pattern: [u8; 16], pitch: u8) {
+        self.pattern = pattern;
+        self.step = pattern_rate(pitch) / self.sample_rate;
+    }
+
+    /// Fills `samples` with the pattern at the given volume, or with silence if `playing` is false.
+    pub fn fill(&mut self, samples: &mut [f32], playing: bool, volume: f32) {
+        for sample in samples.iter_mut() {
+            let bit = self.position as usize;
+            let set = self.pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            *sample = match (playing, set) {
+                (false, _) => 0.0,
+                (true, true) => volume,
+                (true, false) => -volume,
+            };
+            self.position = (self.position + self.step) % 128.0;
+        }
+    }
+}
+
+/// Frequency of the square wave played by programs without an audio pattern.
+pub const BUZZER_FREQUENCY: f32 = 440.0;
+
+/// An [`Audio`] sink producing the samples to play: a square wave while the buzzer sounds, or the
+/// XO-CHIP audio pattern once a program loaded one.
+///
+/// Frontends usually share it between the CPU and the thread feeding their sound card, which
+/// calls [`fill`](Buzzer::fill) for every block of samples.
+pub struct Buzzer {
+    playing: bool,
+    square: SquareWave,
+    pattern: Option<PatternWave>,
+    sample_rate: u32,
+}
+
+impl Buzzer {
+    pub fn new(sample_rate: u32) -> Buzzer {
+        Buzzer {
+            playing: false,
+            square: SquareWave::new(BUZZER_FREQUENCY, sample_rate),
+            pattern: None,
+            sample_rate,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Fills `samples` with what the buzzer sounds like right now, silence while it's off.
+    pub fn fill(&mut self, samples: &mut [f32], volume: f32) {
+        match &mut self.pattern {
+            Some(pattern) => pattern.fill(samples, self.playing, volume),
+            None => self.square.fill(samples, self.playing, volume),
+        }
+    }
+}
+
+impl Audio for Buzzer {
+    fn set_tone(&mut self, playing: bool) {
+        self.playing = playing;
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8; 16], pitch: u8) {
+        match &mut self.pattern {
+            Some(wave) => wave.set_pattern(*pattern, pitch),
+            None => self.pattern = Some(PatternWave::new(*pattern, pitch, self.sample_rate)),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use chipper::asm::assemble_file;
+
+const USAGE: &str = "\
+Usage: chipper-asm [OPTIONS] <SOURCE>
+
+Assembles a CHIP-8 program written in the syntax chipper-disasm prints into a ROM.
+
+Arguments:
+  <SOURCE>              Path to the assembly source
+
+Options:
+  -o, --output <FILE>   Where to write the ROM (default: SOURCE with the extension .ch8)
+  -h, --help            Print this message";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut source_path = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "-o" | "--output" => {
+                output = Some(args.next().unwrap_or_else(|| usage_error(&format!("{} requires a value", arg))));
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option '{}'", arg)),
+            _ if source_path.is_some() => usage_error(&format!("unexpected argument '{}'", arg)),
+            _ => source_path = Some(arg),
+        }
+    }
+
+    let source_path = source_path.unwrap_or_else(|| usage_error("no source given"));
+    let output = output.unwrap_or_else(|| Path::new(&source_path).with_extension("ch8").display().to_string());
+    if Path::new(&output) == Path::new(&source_path) {
+        usage_error("the ROM would overwrite the source, choose another path with --output");
+    }
+
+    let program = assemble_file(&source_path).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+    if let Err(e) = fs::write(&output, &program) {
+        eprintln!("error: could not write '{}': {}", output, e);
+        process::exit(1);
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,55 @@
+use std::process;
+
+use chipper::disasm::disassemble;
+use chipper::{Mode, ROM};
+
+const USAGE: &str = "\
+Usage: chipper-disasm [OPTIONS] <ROM>
+
+Prints the instructions of a CHIP-8 ROM, following the control flow from 0x200 to tell code from
+data.
+
+Arguments:
+  <ROM>                 Path to the CHIP-8 ROM to disassemble
+
+Options:
+  --mode <MODE>         Instruction set, chip8, schip for SUPER-CHIP or xochip for
+                        XO-CHIP (default: chip8)
+  -h, --help            Print this message";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut rom_path = None;
+    let mut mode = Mode::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--mode" => {
+                mode = args.next()
+                    .ok_or_else(|| String::from("--mode requires a value"))
+                    .and_then(|value| value.parse())
+                    .unwrap_or_else(|e| usage_error(&e));
+            }
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option '{}'", arg)),
+            _ if rom_path.is_some() => usage_error(&format!("unexpected argument '{}'", arg)),
+            _ => rom_path = Some(arg),
+        }
+    }
+
+    let rom_path = rom_path.unwrap_or_else(|| usage_error("no ROM given"));
+    let rom = ROM::new(&rom_path).unwrap_or_else(|e| {
+        eprintln!("error: could not load ROM '{}': {}", rom_path, e);
+        process::exit(1);
+    });
+
+    print!("{}", disassemble(&rom.data, mode));
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use chipper::cpu::DEFAULT_INSTRUCTIONS_PER_FRAME;
+use chipper::rewind;
+use chipper::{Mode, Palette, Quirks};
+use chipper::scale::Scaling;
+use chipper::scheduler::FRAME_RATE;
+
+pub const USAGE: &str = "\
+Usage: chipper [OPTIONS] <ROM>
+
+Arguments:
+  <ROM>                 Path to the CHIP-8 ROM to run
+
+Options:
+  --scale <N>           Size of each CHIP-8 pixel in window pixels when the window opens,
+                        which can be resized afterwards (default: 10)
+  --scaling <MODE>      How the screen fills the window, integer for whole multiples of its
+                        size or fit to fill as much as possible (default: integer)
+  --fullscreen          Start in fullscreen, toggled with F11 in the window
+  --fullscreen-size <WIDTHxHEIGHT>
+                        Size of the fullscreen window, the resolution of the monitor
+                        (default: 1920x1080)
+  --hz <N>              Instructions executed per second, rounded to whole instructions
+                        per frame (default: 660)
+  --cpi <N>             Instructions executed per 60 Hz frame, alternative to --hz (default: 11)
+  --debug               Print every executed instruction
+  --debugger            Step through the program in an interactive debugger on the terminal
+                        instead of opening a window
+  --mode <MODE>         Instruction set, chip8, schip for SUPER-CHIP or xochip for
+                        XO-CHIP (default: chip8)
+  --quirks <SPEC>       Behaviour of ambiguous opcodes, a preset (chipper, vip, chip48, schip,
+                        xochip) followed by flags to enable or disable (shift-vx,
+                        increment-i, jump-vx, wrap-sprites, vf-reset, i-overflow, prefix
+                        with no- to disable), e.g. vip,wrap-sprites (default: chipper)
+  --palette <PALETTE>   Screen colours, a preset (chipper, classic, amber, green, lcd) or
+                        the background and foreground colours as RGB hex, e.g.
+                        000000,c8c864, optionally followed by the colours of the second
+                        XO-CHIP plane and of both planes overlapping (default: chipper)
+  --theme <FILE>        Read the screen colours from a theme file instead of --palette
+  --seed <N>            Seed for the random numbers of CXNN, which makes runs reproducible
+                        (default: a random seed)
+  --trace <FILE>        Record every executed instruction with the registers to FILE, in
+                        binary (.bin) or text (any other name, or - for stdout)
+  --state <FILE>        Save state file written with F5 and loaded with F9 in the window
+                        (default: the ROM path followed by .state)
+  --load-state          Start from the save state in the --state file
+  --rewind <SECONDS>    Gameplay kept to rewind while holding Backspace in the window, 0 to
+                        turn rewinding off (default: 10)
+  --record <FILE>       Record the keys pressed in every frame to the movie FILE
+  --replay <FILE>       Replay the movie FILE instead of reading the keyboard, with the mode,
+                        quirks, instruction rate and seed it was recorded with
+  --headless            Run without opening a window, as fast as possible
+
+Headless options:
+  --cycles <N>          Stop after N instructions (default: 1000000, or the end of the movie
+                        with --replay)
+  --until-pc <ADDR>     Stop once the program counter reaches the hex address ADDR
+  --until-halt          Stop once the program jumps to its own address
+  --dump <FILE>         Write the final screen to FILE as PBM (.pbm), PNG (.png) or
+                        ASCII art (any other name, or - for stdout)
+  -h, --help            Print this message";
+
+const DEFAULT_SCALE: usize = 10;
+const DEFAULT_FULLSCREEN_SIZE: (usize, usize) = (1920, 1080);
+const DEFAULT_HEADLESS_CYCLES: usize = 1_000_000;
+
+#[cfg_attr(not(feature = "window"), allow(dead_code))]
+pub struct Options {
+    pub rom_path: String,
+    pub scale: usize,
+    pub scaling: Scaling,
+    pub fullscreen: bool,
+    pub fullscreen_size: (usize, usize),
+    pub instructions_per_frame: usize,
+    pub debug: bool,
+    pub debugger: bool,
+    pub mode: Mode,
+    pub quirks: Quirks,
+    pub palette: Palette,
+    pub theme: Option<String>,
+    pub seed: Option<u64>,
+    pub trace: Option<String>,
+    pub state_path: String,
+    pub load_state: bool,
+    pub rewind_seconds: usize,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub headless: bool,
+    pub cycles: usize,
+    pub until_pc: Option<u16>,
+    pub until_halt: bool,
+    pub dump: Option<String>,
+}
+
+pub enum CliError {
+    Help,
+    Usage(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::Usage(message) => write!(f, "error: {}\n\n{}", message, USAGE),
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item=String>>(mut args: I) -> Result<Options, CliError> {
+        let mut rom_path = None;
+        let mut scale = DEFAULT_SCALE;
+        let mut scaling = Scaling::default();
+        let mut fullscreen = false;
+        let mut fullscreen_size = DEFAULT_FULLSCREEN_SIZE;
+        let mut instructions_per_frame = None;
+        let mut debug = false;
+        let mut debugger = false;
+        let mut mode = Mode::default();
+        let mut quirks = Quirks::default();
+        let mut palette = Palette::default();
+        let mut theme = None;
+        let mut seed = None;
+        let mut trace = None;
+        let mut state_path = None;
+        let mut load_state = false;
+        let mut rewind_seconds = rewind::DEFAULT_SECONDS;
+        let mut record = None;
+        let mut replay = None;
+        let mut headless = false;
+        let mut cycles = None;
+        let mut until_pc = None;
+        let mut until_halt = false;
+        let mut dump = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(CliError::Help),
+                "--scale" => {
+                    scale = parse_number(&arg, &mut args)?;
+                    if scale == 0 {
+                        return Err(CliError::Usage(String::from("--scale must be at least 1")));
+                    }
+                }
+                "--hz" | "--cpi" => {
+                    if instructions_per_frame.is_some() {
+                        return Err(CliError::Usage(String::from("--hz and --cpi may only be given once")));
+                    }
+                    let value: usize = parse_number(&arg, &mut args)?;
+                    let per_frame = if arg == "--hz" {
+                        (value + FRAME_RATE as usize / 2) / FRAME_RATE as usize
+                    } else {
+                        value
+                    };
+                    if per_frame == 0 {
+                        return Err(CliError::Usage(format!("{} is too low to run at least one instruction per frame", arg)));
+                    }
+                    instructions_per_frame = Some(per_frame);
+                }
+                "--scaling" => scaling = value_for(&arg, &mut args)?.parse().map_err(CliError::Usage)?,
+                "--fullscreen" => fullscreen = true,
+                "--fullscreen-size" => fullscreen_size = parse_size(&value_for(&arg, &mut args)?)?,
+                "--debug" => debug = true,
+                "--debugger" => debugger = true,
+                "--mode" => mode = value_for(&arg, &mut args)?.parse().map_err(CliError::Usage)?,
+                "--quirks" => {
+                    quirks = value_for(&arg, &mut args)?.parse()
+                        .map_err(|e| CliError::Usage(format!("{}", e)))?;
+                }
+                "--palette" => {
+                    palette = value_for(&arg, &mut args)?.parse()
+                        .map_err(|e| CliError::Usage(format!("{}", e)))?;
+                }
+                "--theme" => theme = Some(value_for(&arg, &mut args)?),
+                "--seed" => seed = Some(parse_number(&arg, &mut args)?),
+                "--trace" => trace = Some(value_for(&arg, &mut args)?),
+                "--state" => state_path = Some(value_for(&arg, &mut args)?),
+                "--load-state" => load_state = true,
+                "--rewind" => rewind_seconds = parse_number(&arg, &mut args)?,
+                "--record" => record = Some(value_for(&arg, &mut args)?),
+                "--replay" => replay = Some(value_for(&arg, &mut args)?),
+                "--headless" => headless = true,
+                "--cycles" => cycles = Some(parse_number(&arg, &mut args)?),
+                "--until-pc" => until_pc = Some(parse_address(&value_for(&arg, &mut args)?)?),
+                "--until-halt" => until_halt = true,
+                "--dump" => dump = Some(value_for(&arg, &mut args)?),
+                _ if arg.starts_with('-') => {
+                    return Err(CliError::Usage(format!("unknown option '{}'", arg)));
+                }
+                _ => {
+                    if rom_path.is_some() {
+                        return Err(CliError::Usage(format!("unexpected argument '{}'", arg)));
+                    }
+                    rom_path = Some(arg);
+                }
+            }
+        }
+
+        let rom_path = rom_path.ok_or_else(|| CliError::Usage(String::from("no ROM given")))?;
+
+        if headless && debugger {
+            return Err(CliError::Usage(String::from("--debugger can't be combined with --headless")));
+        }
+        if record.is_some() && (headless || debugger) {
+            return Err(CliError::Usage(String::from("--record requires the window, it can't be combined with --headless or --debugger")));
+        }
+        if replay.is_some() && (record.is_some() || debugger) {
+            return Err(CliError::Usage(String::from("--replay can't be combined with --record or --debugger")));
+        }
+        if load_state && (record.is_some() || replay.is_some()) {
+            return Err(CliError::Usage(String::from("movies always start from the beginning, --load-state can't be combined with --record or --replay")));
+        }
+        // Movies bring their own mode, which the address isn't checked against
+        if let Some(address) = until_pc.filter(|_| replay.is_none()) {
+            if address as usize >= mode.memory_size() {
+                return Err(CliError::Usage(format!("--until-pc {:#X} is past the end of the {} bytes of memory of --mode", address, mode.memory_size())));
+            }
+        }
+        if !headless && (cycles.is_some() || until_pc.is_some() || until_halt || dump.is_some()) {
+            return Err(CliError::Usage(String::from("--cycles, --until-pc, --until-halt and --dump require --headless")));
+        }
+
+        // Replays run to the end of the movie unless told otherwise
+        let cycles = cycles.unwrap_or(if replay.is_some() { usize::MAX } else { DEFAULT_HEADLESS_CYCLES });
+        let state_path = state_path.unwrap_or_else(|| format!("{}.state", rom_path));
+
+        Ok(Options {
+            rom_path,
+            scale,
+            scaling,
+            fullscreen,
+            fullscreen_size,
+            instructions_per_frame: instructions_per_frame.unwrap_or(DEFAULT_INSTRUCTIONS_PER_FRAME),
+            debug,
+            debugger,
+            mode,
+            quirks,
+            palette,
+            theme,
+            seed,
+            trace,
+            state_path,
+            load_state,
+            rewind_seconds,
+            record,
+            replay,
+            headless,
+            cycles,
+            until_pc,
+            until_halt,
+            dump,
+        })
+    }
+}
+
+fn value_for<I: Iterator<Item=String>>(option: &str, args: &mut I) -> Result<String, CliError> {
+    args.next().ok_or_else(|| CliError::Usage(format!("{} requires a value", option)))
+}
+
+fn parse_number<T: std::str::FromStr, I: Iterator<Item=String>>(option: &str, args: &mut I) -> Result<T, CliError> {
+    let value = value_for(option, args)?;
+    value.parse()
+        .map_err(|_| CliError::Usage(format!("invalid value '{}' for {}", value, option)))
+}
+
+fn parse_address(value: &str) -> Result<u16, CliError> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| CliError::Usage(format!("invalid address '{}', expected a hex value up to FFFF", value)))
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), CliError> {
+    let size = value.split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(CliError::Usage(format!("invalid size '{}', expected WIDTHxHEIGHT like 1920x1080", value))),
+    }
+}
@@ -1,64 +1,384 @@
-use std::thread::sleep;
-use std::time;
-// use std::time::Duration;
-
-use rand::Rng;
-use rand::rngs::ThreadRng;
-
-use crate::display::Display;
+use crate::audio::{Audio, NullAudio, DEFAULT_PITCH};
+use crate::display::{Display, HEIGHT, HIRES_HEIGHT, HIRES_WIDTH, WIDTH};
+use crate::error::{ChipError, Result};
+use crate::framebuffer::{Framebuffer, ALL_PLANES};
+use crate::instruction::{decode, decode_for, Instruction};
 use crate::keyboard::Keyboard;
+use crate::quirks::Quirks;
+use crate::rng::Rng;
 use crate::rom::ROM;
-use crate::{SCALE_FACTOR, WIDTH, HEIGHT};
+use crate::scheduler::Scheduler;
+use crate::state::SaveState;
+use crate::trace::{Trace, TraceEntry};
+
+/// Prints a message when debug mode is enabled, the message isn't even formatted otherwise.
+macro_rules! debug {
+    ($cpu:expr, $($arg:tt)*) => {
+        if $cpu.debug_mode {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Instructions executed per 60 Hz frame unless configured otherwise, roughly 660 per second.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 11;
+
+/// Address of the 8x10 SUPER-CHIP font, placed right after the 4x5 CHIP-8 font.
+const LARGE_FONT_ADDRESS: usize = 0x50;
+
+const LARGE_FONT: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// The CHIP-8 variant whose instruction set the CPU understands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// The original CHIP-8 instruction set.
+    #[default]
+    Chip8,
+    /// SUPER-CHIP 1.1, which adds a 128x64 high resolution mode, scrolling, 16x16 sprites, a
+    /// large font and the RPL user flags.
+    SuperChip,
+    /// XO-CHIP, which extends SUPER-CHIP with 64 KiB of memory, a second drawing plane for four
+    /// colours, audio patterns and a few more instructions.
+    XoChip,
+}
+
+impl Mode {
+    fn supports_schip(self) -> bool {
+        self != Mode::Chip8
+    }
+
+    fn supports_xochip(self) -> bool {
+        self == Mode::XoChip
+    }
+
+    /// Whether the instruction set of `mode` is part of this mode's instruction set.
+    pub fn supports(self, mode: Mode) -> bool {
+        match mode {
+            Mode::Chip8 => true,
+            Mode::SuperChip => self.supports_schip(),
+            Mode::XoChip => self.supports_xochip(),
+        }
+    }
 
-const BUFFER_SIZE: usize = (WIDTH / SCALE_FACTOR) * (HEIGHT / SCALE_FACTOR);
+    /// Size of the memory in bytes.
+    pub fn memory_size(self) -> usize {
+        if self.supports_xochip() {
+            65536
+        } else {
+            4096
+        }
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
 
-pub struct CPU {
+    fn from_str(s: &str) -> std::result::Result<Mode, String> {
+        match s {
+            "chip8" => Ok(Mode::Chip8),
+            "schip" => Ok(Mode::SuperChip),
+            "xochip" => Ok(Mode::XoChip),
+            _ => Err(format!("unknown mode '{}', expected chip8, schip or xochip", s)),
+        }
+    }
+}
+
+pub struct CPU<K, D, A = NullAudio> {
     v: [u8; 16],
     i: u16,
     pc: u16,
-    memory: [u8; 4096],
+    memory: Vec<u8>,
     delay_timer: u8,
     sound_timer: u8,
     stack: [u16; 16],
     sp: usize,
+    awaited_key: Option<u8>,
+    rpl_flags: [u8; 16],
+    planes: u8,
+    audio_pattern: [u8; 16],
+    pitch: u8,
+    exited: bool,
+    mode: Mode,
+    quirks: Quirks,
     debug_mode: bool,
-    keyboard: Keyboard,
-    display: Display,
-    rng: ThreadRng,
-    output_buffer: [u32; BUFFER_SIZE],
+    instructions_per_frame: usize,
+    frame_cycles: usize,
+    keyboard: K,
+    display: D,
+    audio: A,
+    tone_playing: bool,
+    rng: Rng,
+    trace: Option<Box<dyn Trace>>,
+    output_buffer: Framebuffer,
 }
 
-fn from_u8_rgb(r: u8, g: u8, b: u8) -> u32 {
-    let (r, g, b) = (r as u32, g as u32, b as u32);
-    (r << 16) | (g << 8) | b
+impl<K: Keyboard, D: Display> CPU<K, D> {
+    pub fn new(keyboard: K, display: D) -> CPU<K, D> {
+        CPU::with_audio(keyboard, display, NullAudio)
+    }
 }
 
-impl CPU {
-    pub fn new(keyboard: Keyboard, display: Display) -> CPU {
-        CPU {
+impl<K: Keyboard, D: Display, A: Audio> CPU<K, D, A> {
+    pub fn with_audio(keyboard: K, display: D, audio: A) -> CPU<K, D, A> {
+        CPU::with_rng(keyboard, display, audio, Rng::from_entropy())
+    }
+
+    /// Creates a CPU drawing its random numbers from `rng`, which makes runs reproducible when
+    /// it's seeded with [`Rng::new`].
+    pub fn with_rng(keyboard: K, display: D, audio: A, rng: Rng) -> CPU<K, D, A> {
+        let mut cpu = CPU {
             v: [0; 16],
             i: 0,
             pc: 0x200,
-            memory: [0; 4096],
+            memory: vec![0; Mode::default().memory_size()],
             delay_timer: 0,
             sound_timer: 0,
             stack: [0; 16],
             sp: 0,
+            awaited_key: None,
+            rpl_flags: [0; 16],
+            planes: 1,
+            audio_pattern: [0; 16],
+            pitch: DEFAULT_PITCH,
+            exited: false,
+            mode: Mode::default(),
+            quirks: Quirks::default(),
             debug_mode: false,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            frame_cycles: 0,
             keyboard,
             display,
-            output_buffer: [0; BUFFER_SIZE],
-            rng: rand::thread_rng(),
+            audio,
+            tone_playing: false,
+            output_buffer: Framebuffer::new(WIDTH, HEIGHT),
+            rng,
+            trace: None,
+        };
+        cpu.load_fontset();
+        cpu
+    }
+
+    /// Copies the program into memory at 0x200, failing if it doesn't fit in the memory of the
+    /// current [`Mode`], so the mode should be selected first.
+    pub fn load_rom(&mut self, rom: ROM) -> Result<()> {
+        let max = self.memory.len() - 0x200;
+        if rom.size > max {
+            return Err(ChipError::RomTooLarge { size: rom.size, max });
+        }
+        self.memory[0x200..0x200 + rom.size].copy_from_slice(&rom.data[..rom.size]);
+        Ok(())
+    }
+
+    pub fn keyboard(&self) -> &K {
+        &self.keyboard
+    }
+
+    pub fn keyboard_mut(&mut self) -> &mut K {
+        &mut self.keyboard
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    pub fn audio(&self) -> &A {
+        &self.audio
+    }
+
+    pub fn audio_mut(&mut self) -> &mut A {
+        &mut self.audio
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// The index register I.
+    pub fn i(&self) -> u16 {
+        self.i
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /// The general purpose registers V0 to VF.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.v
+    }
+
+    /// The return addresses of the subroutines currently called, innermost last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp]
+    }
+
+    /// The whole memory, including the fonts below 0x200.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// The current contents of the screen, row by row.
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.output_buffer
+    }
+
+    /// Whether the program ended with the SUPER-CHIP exit instruction `00FD`.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Whether the program has come to a halt by exiting or by jumping to its own address, which
+    /// is how most programs (and test ROMs in particular) end.
+    pub fn is_halted(&self) -> bool {
+        if self.exited {
+            return true;
+        }
+        let pc = self.pc as usize;
+        if pc + 1 >= self.memory.len() {
+            return false;
+        }
+        let opcode = ((self.memory[pc] as u16) << 8) | self.memory[pc + 1] as u16;
+        opcode == 0x1000 | self.pc
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Selects the instruction set, extended instructions are ignored or rejected in modes that
+    /// don't support them. Switching to or from [`Mode::XoChip`] resizes the memory.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.memory.resize(mode.memory_size(), 0);
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Takes a snapshot of the machine, which [`load_state`](CPU::load_state) restores.
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            mode: self.mode,
+            v: self.v,
+            i: self.i,
+            pc: self.pc,
+            memory: self.memory.clone(),
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            stack: self.stack,
+            sp: self.sp,
+            awaited_key: self.awaited_key,
+            rpl_flags: self.rpl_flags,
+            planes: self.planes,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            exited: self.exited,
+            frame_cycles: self.frame_cycles,
+            output_buffer: self.output_buffer.clone(),
+            rng: self.rng,
+        }
+    }
+
+    /// Puts the machine back into a state taken with [`save_state`](CPU::save_state), including
+    /// its mode and random number generator, and shows the saved screen. The quirks and
+    /// instruction rate are kept.
+    pub fn load_state(&mut self, state: &SaveState) {
+        self.mode = state.mode;
+        self.v = state.v;
+        self.i = state.i;
+        self.pc = state.pc;
+        self.memory.clone_from(&state.memory);
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.stack = state.stack;
+        self.sp = state.sp;
+        self.awaited_key = state.awaited_key;
+        self.rpl_flags = state.rpl_flags;
+        self.planes = state.planes;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.exited = state.exited;
+        self.frame_cycles = state.frame_cycles;
+        self.output_buffer.clone_from(&state.output_buffer);
+        self.rng = state.rng;
+
+        if self.mode.supports_xochip() {
+            self.audio.set_pattern(&self.audio_pattern, self.pitch);
+        }
+        self.update_tone();
+        self.display.update_buffer(&self.output_buffer);
+    }
+
+    /// The generator used by `CXNN`.
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    pub fn enable_debug(&mut self) {
+        self.debug_mode = true;
+    }
+
+    /// Records every instruction executed from now on to `trace`, or stops tracing with `None`.
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Trace>>) {
+        self.trace = trace;
+    }
+
+    /// Writes out the buffered part of the trace, if tracing.
+    pub fn flush_trace(&mut self) -> Result<()> {
+        if let Some(trace) = &mut self.trace {
+            trace.flush()?;
         }
+        Ok(())
     }
 
-    pub fn load_rom(&mut self, rom: ROM) {
-        self.memory[0x200..].clone_from_slice(&rom.data);
+    pub fn instructions_per_frame(&self) -> usize {
+        self.instructions_per_frame
     }
 
-    // pub fn enable_debug(&mut self) {
-    //     self.debug_mode = true;
-    // }
+    /// Sets how many instructions are executed for every tick of the 60 Hz timers.
+    pub fn set_instructions_per_frame(&mut self, instructions: usize) {
+        self.instructions_per_frame = instructions.max(1);
+    }
+
+    /// Sets the instruction rate in instructions per second, rounded to whole instructions per frame.
+    pub fn set_clock_rate(&mut self, hz: u32) {
+        let frame_rate = crate::scheduler::FRAME_RATE;
+        self.set_instructions_per_frame(((hz + frame_rate / 2) / frame_rate) as usize);
+    }
 
     pub fn load_fontset(&mut self) {
         // 0
@@ -157,243 +477,444 @@ impl CPU {
         self.memory[77] = 0xF0;
         self.memory[78] = 0x80;
         self.memory[79] = 0x80;
+
+        self.memory[LARGE_FONT_ADDRESS..LARGE_FONT_ADDRESS + LARGE_FONT.len()].copy_from_slice(&LARGE_FONT);
     }
 
-    pub fn run(&mut self) {
-        const CYCLES_TO_RUN: usize = 1_000_000;
-        let mut cycles_ran = 0;
+    /// Runs the program in real time until it exits or fails.
+    pub fn run(&mut self) -> Result<()> {
+        let mut scheduler = Scheduler::new();
+
+        while !self.exited {
+            for _ in 0..scheduler.wait_for_frame() {
+                self.run_frame()?;
+            }
+        }
+        Ok(())
+    }
 
-        self.load_fontset();
+    /// Executes instructions up to the end of the current frame, which ticks the timers.
+    pub fn run_frame(&mut self) -> Result<()> {
+        while !self.exited {
+            self.step()?;
 
-        loop {
-            if self.pc >= 4096 {
+            if self.frame_cycles == 0 {
                 break;
             }
-            self.execute_op();
-            sleep(time::Duration::from_micros(500));
+        }
+        Ok(())
+    }
 
-            if self.delay_timer > 0 {
-                self.delay_timer -= 1;
-            }
+    /// Runs as fast as possible until `max_cycles` instructions have been executed or `stop`
+    /// returns true, returning the number of instructions executed.
+    ///
+    /// `stop` is checked before every instruction.
+    pub fn run_until<F: FnMut(&Self) -> bool>(&mut self, max_cycles: usize, mut stop: F) -> Result<usize> {
+        let mut cycles_ran = 0;
 
+        while cycles_ran < max_cycles && !self.exited && !stop(self) {
+            self.step()?;
             cycles_ran += 1;
-            self.print_debug(format!("{0}", cycles_ran));
+        }
 
-            if cycles_ran == CYCLES_TO_RUN {
-                break;
-            }
+        Ok(cycles_ran)
+    }
+
+    /// Executes a single instruction, ticking the timers once every
+    /// [`instructions_per_frame`](CPU::instructions_per_frame) instructions.
+    ///
+    /// When the instruction fails the program counter is left pointing at it.
+    pub fn step(&mut self) -> Result<()> {
+        if self.exited {
+            return Ok(());
+        }
+        if self.frame_cycles == 0 {
+            self.keyboard.start_frame();
+        }
+        let pc = self.pc;
+        if let Err(e) = self.execute_op() {
+            self.pc = pc;
+            return Err(e);
+        }
+
+        self.frame_cycles += 1;
+        if self.frame_cycles >= self.instructions_per_frame {
+            self.frame_cycles = 0;
+            self.tick_timers();
+        }
+        Ok(())
+    }
+
+    /// Counts the delay and sound timers down by one, which happens at 60 Hz.
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+        self.update_tone();
+    }
+
+    fn update_tone(&mut self) {
+        let playing = self.sound_timer > 0;
+        if playing != self.tone_playing {
+            self.tone_playing = playing;
+            self.audio.set_tone(playing);
+        }
+    }
+
+    /// Clears VF after the logical operations with the `vf_reset` quirk.
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
         }
     }
 
-    pub fn print_debug(&mut self, message: String) {
-        if self.debug_mode {
-            println!("{}", message);
+    /// Skips the next instruction, which is four bytes long if it's the XO-CHIP `F000 NNNN`.
+    fn skip_instruction(&mut self) {
+        let pc = self.pc as usize;
+        if self.mode.supports_xochip() && pc + 1 < self.memory.len() {
+            let opcode = ((self.memory[pc] as u16) << 8) | self.memory[pc + 1] as u16;
+            self.pc = self.wrap_address(self.pc.wrapping_add(decode(opcode).size()));
+        } else {
+            self.pc = self.wrap_address(self.pc.wrapping_add(2));
         }
     }
 
-    pub fn execute_op(&mut self) {
-        self.print_debug(format!("-------------------\nPC: {:#06X?}", self.pc));
-        let opcode = ((self.memory[self.pc as usize] as u16) << 8) | self.memory[(self.pc + 1) as usize] as u16;
-        self.print_debug(format!("OPCODE: {:#06X?}", opcode));
+    /// Wraps an address computed by the program around the end of memory, so I and the program
+    /// counter stay 12 bit addresses outside of XO-CHIP, which addresses 16 bits.
+    fn wrap_address(&self, address: u16) -> u16 {
+        address & (self.memory.len() - 1) as u16
+    }
 
-        self.pc += 2;
+    /// The state right before the instruction at `pc` executes, with the word after `F000 NNNN`.
+    fn trace_entry(&self, pc: u16, opcode: u16) -> TraceEntry {
+        let next = self.wrap_address(pc.wrapping_add(2)) as usize;
+        TraceEntry {
+            pc,
+            opcode,
+            v: self.v,
+            i: self.i,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            mode: self.mode,
+            operand: match decode_for(opcode, self.mode) {
+                Instruction::LdILong => self.memory.get(next..next + 2)
+                    .map_or(0, |word| u16::from_be_bytes([word[0], word[1]])),
+                _ => 0,
+            },
+        }
+    }
 
-        let op1 = ((opcode & 0xF000) >> 12) as u8;
-        let op2 = ((opcode & 0x0F00) >> 8) as usize;
-        let op3 = ((opcode & 0x00F0) >> 4) as u8;
-        let op4 = (opcode & 0x000F) as u8;
+    /// Checks that the `len` bytes starting at `address` are in memory, for the instruction at `pc`.
+    fn check_bounds(&self, pc: u16, address: usize, len: usize) -> Result<()> {
+        if address + len > self.memory.len() {
+            return Err(ChipError::MemoryOutOfBounds { pc, address: address.max(self.memory.len()) });
+        }
+        Ok(())
+    }
 
-        let nnn = opcode & 0x0FFF;
-        let nn = (opcode & 0x00FF) as u8;
+    pub fn execute_op(&mut self) -> Result<()> {
+        debug!(self, "-------------------\nPC: {:#06X?}", self.pc);
+        let pc = self.pc;
+        self.check_bounds(pc, pc as usize, 2)?;
+        let opcode = ((self.memory[pc as usize] as u16) << 8) | self.memory[pc as usize + 1] as u16;
+        debug!(self, "OPCODE: {:#06X?}", opcode);
+
+        if self.trace.is_some() {
+            let entry = self.trace_entry(pc, opcode);
+            if let Some(trace) = &mut self.trace {
+                trace.record(&entry)?;
+            }
+        }
 
-        // self.disassemble_op(opcode);
+        self.pc = self.wrap_address(pc.wrapping_add(2));
 
-        match (op1, op2, op3, op4) {
-            (0x0, 0x0, 0xE, 0x0) => {
-                self.print_debug(format!("Clear screen"));
+        match decode_for(opcode, self.mode) {
+            Instruction::Cls => {
+                debug!(self, "Clear screen");
 
-                for i in 0..self.output_buffer.len() {
-                    self.output_buffer[i] = 0;
-                }
+                self.output_buffer.clear(self.planes);
                 self.display.update_buffer(&self.output_buffer);
             }
-            (0x0, 0x0, 0xE, 0xE) => {
-                self.print_debug(format!("Return from a subroutine"));
+            Instruction::Ret => {
+                debug!(self, "Return from a subroutine");
 
+                if self.sp == 0 {
+                    return Err(ChipError::StackUnderflow { pc });
+                }
                 self.sp -= 1;
                 self.pc = self.stack[self.sp];
             }
+            Instruction::Scd(n) => {
+                debug!(self, "Scroll the screen down {} lines", n);
+
+                self.output_buffer.scroll_down(n as usize, self.planes);
+                self.display.update_buffer(&self.output_buffer);
+            }
+            Instruction::Scu(n) => {
+                debug!(self, "Scroll the screen up {} lines", n);
+
+                self.output_buffer.scroll_up(n as usize, self.planes);
+                self.display.update_buffer(&self.output_buffer);
+            }
+            Instruction::Scr => {
+                debug!(self, "Scroll the screen right 4 pixels");
+
+                self.output_buffer.scroll_right(4, self.planes);
+                self.display.update_buffer(&self.output_buffer);
+            }
+            Instruction::Scl => {
+                debug!(self, "Scroll the screen left 4 pixels");
+
+                self.output_buffer.scroll_left(4, self.planes);
+                self.display.update_buffer(&self.output_buffer);
+            }
+            Instruction::Exit => {
+                debug!(self, "Exit the interpreter");
+
+                self.exited = true;
+                self.pc = pc;
+            }
+            Instruction::Low => {
+                debug!(self, "Switch to low resolution mode");
+
+                self.output_buffer.resize(WIDTH, HEIGHT);
+                self.display.update_buffer(&self.output_buffer);
+            }
+            Instruction::High => {
+                debug!(self, "Switch to high resolution mode");
+
+                self.output_buffer.resize(HIRES_WIDTH, HIRES_HEIGHT);
+                self.display.update_buffer(&self.output_buffer);
+            }
             // This instruction only applies to original CHIP-8 hardware
-            (0x0, _, _, _) => {
-                self.print_debug(format!("Execute machine language subroutine at address {:#05X?}", nnn));
+            Instruction::Sys(nnn) => {
+                debug!(self, "Execute machine language subroutine at address {:#05X?}", nnn);
             }
-            (0x1, _, _, _) => {
-                self.print_debug(format!("Jump to address {}", nnn));
+            Instruction::Jp(nnn) => {
+                debug!(self, "Jump to address {}", nnn);
 
                 self.pc = nnn;
             }
-            (0x2, _, _, _) => {
-                self.print_debug(format!("Execute subroutine at address {:#05X?}", nnn));
+            Instruction::Call(nnn) => {
+                debug!(self, "Execute subroutine at address {:#05X?}", nnn);
 
+                if self.sp == self.stack.len() {
+                    return Err(ChipError::StackOverflow { pc });
+                }
                 self.stack[self.sp] = self.pc;
                 self.sp += 1;
 
                 self.pc = nnn;
             }
-            (0x3, x, _, _) => {
-                self.print_debug(format!("Skip the following instruction if V{} == {}", x, nn));
+            Instruction::SeVxByte { x, nn } => {
+                let x = x as usize;
+                debug!(self, "Skip the following instruction if V{} == {}", x, nn);
 
                 if self.v[x] == nn {
-                    self.pc += 2;
+                    self.skip_instruction();
                 }
             }
-            (0x4, x, _, _) => {
-                self.print_debug(format!("Skip the following instruction if V{} != {}", x, nn));
+            Instruction::SneVxByte { x, nn } => {
+                let x = x as usize;
+                debug!(self, "Skip the following instruction if V{} != {}", x, nn);
 
                 if self.v[x] != nn {
-                    self.pc += 2;
+                    self.skip_instruction();
                 }
             }
-            (0x5, x, y, 0) => {
-                self.print_debug(format!("Skip the following instruction if V{} == V{}", x, y));
+            Instruction::SeVxVy { x, y } => {
+                let x = x as usize;
+                debug!(self, "Skip the following instruction if V{} == V{}", x, y);
 
                 if self.v[x] == self.v[y as usize] {
-                    self.pc += 2;
+                    self.skip_instruction();
+                }
+            }
+            Instruction::SaveRange { x, y } => {
+                let x = x as usize;
+                debug!(self, "Store registers V{} through V{} in memory starting at location {:#06X?}.", x, y, self.i);
+
+                self.check_bounds(pc, self.i as usize, register_range(x, y as usize).count())?;
+                for (offset, reg) in register_range(x, y as usize).enumerate() {
+                    self.memory[self.i as usize + offset] = self.v[reg];
                 }
             }
-            (0x6, x, _, _) => {
-                self.print_debug(format!("Store {} in register V{}", nn, x));
+            Instruction::LoadRange { x, y } => {
+                let x = x as usize;
+                debug!(self, "Read registers V{} through V{} from memory starting at location {:#06X?}.", x, y, self.i);
+
+                self.check_bounds(pc, self.i as usize, register_range(x, y as usize).count())?;
+                for (offset, reg) in register_range(x, y as usize).enumerate() {
+                    self.v[reg] = self.memory[self.i as usize + offset];
+                }
+            }
+            Instruction::LdVxByte { x, nn } => {
+                let x = x as usize;
+                debug!(self, "Store {} in register V{}", nn, x);
 
                 self.v[x] = nn;
             }
-            (0x7, x, _, _) => {
-                self.print_debug(format!("Add {} to register V{}", nn, x));
+            Instruction::AddVxByte { x, nn } => {
+                let x = x as usize;
+                debug!(self, "Add {} to register V{}", nn, x);
 
-                self.v[x] = (self.v[x] as u8).wrapping_add(nn);
+                self.v[x] = self.v[x].wrapping_add(nn);
             }
-            (0x8, x, y, 0x0) => {
-                self.print_debug(format!("Store V{} in V{}", y, x));
+            Instruction::LdVxVy { x, y } => {
+                let x = x as usize;
+                debug!(self, "Store V{} in V{}", y, x);
 
                 self.v[x] = self.v[y as usize]
             }
-            (0x8, x, y, 0x1) => {
-                self.print_debug(format!("Set V{} to V{} | V{}", x, x, y));
+            Instruction::Or { x, y } => {
+                let x = x as usize;
+                debug!(self, "Set V{} to V{} | V{}", x, x, y);
 
                 self.v[x] |= self.v[y as usize];
+                self.reset_vf();
             }
-            (0x8, x, y, 0x2) => {
-                self.print_debug(format!("Set V{} to V{} & V{}", x, x, y));
+            Instruction::And { x, y } => {
+                let x = x as usize;
+                debug!(self, "Set V{} to V{} & V{}", x, x, y);
 
                 self.v[x] &= self.v[y as usize];
+                self.reset_vf();
             }
-            (0x8, x, y, 0x3) => {
-                self.print_debug(format!("Set V{} to V{} ^ V{}", x, x, y));
+            Instruction::Xor { x, y } => {
+                let x = x as usize;
+                debug!(self, "Set V{} to V{} ^ V{}", x, x, y);
 
                 self.v[x] ^= self.v[y as usize];
+                self.reset_vf();
             }
-            (0x8, x, y, 0x4) => {
-                self.print_debug(format!("Add the value of register V{} to register V{}\n\tSet VF to 01 if a carry occurs\n\tSet VF to 00 if a carry does not occur", y, x));
-
-                let new_val: u16 = self.v[x] as u16 + self.v[y as usize] as u16;
-                self.v[0xF] = if new_val >= (u8::MAX as u16) {
-                    1
-                } else {
-                    0
-                };
-
-                self.v[x] = (new_val & 0xFF) as u8;
+            // The flag is written after the result, so it wins when VF is the destination, and
+            // the operands are read before either is written, so VF also works as an operand
+            Instruction::AddVxVy { x, y } => {
+                let x = x as usize;
+                debug!(self, "Add the value of register V{} to register V{}\n\tSet VF to 01 if a carry occurs\n\tSet VF to 00 if a carry does not occur", y, x);
+
+                let (result, carry) = self.v[x].overflowing_add(self.v[y as usize]);
+                self.v[x] = result;
+                self.v[0xF] = carry as u8;
             }
-            (0x8, x, y, 0x5) => {
-                self.print_debug(format!("Subtract the value of register V{} from register V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", y, x));
+            Instruction::Sub { x, y } => {
+                let x = x as usize;
+                debug!(self, "Subtract the value of register V{} from register V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", y, x);
 
-                self.v[0xF] = if self.v[x] > self.v[y as usize] {
-                    1
-                } else {
-                    // No borrow occurred
-                    0
-                };
-
-                self.v[x] = (self.v[x] as u8).wrapping_sub(self.v[y as usize]);
+                let (result, borrow) = self.v[x].overflowing_sub(self.v[y as usize]);
+                self.v[x] = result;
+                self.v[0xF] = !borrow as u8;
             }
-            (0x8, x, _, 0x6) => {
-                self.print_debug(format!("Shift the value of register V{} right one bit\n\tSet register VF to the least significant bit prior to the shift", x));
-
-                self.v[0xF] = self.v[x] & 0x1;
-                self.v[x] >>= 1;
+            Instruction::Shr { x, y } => {
+                let x = x as usize;
+                let source = if self.quirks.shift_vx { x } else { y as usize };
+                debug!(self, "Store the value of register V{} shifted right one bit in register V{}\n\tSet register VF to the least significant bit prior to the shift", source, x);
+
+                let value = self.v[source];
+                self.v[x] = value >> 1;
+                self.v[0xF] = value & 0x1;
             }
-            (0x8, x, y, 0x7) => {
-                self.print_debug(format!("Set register V{} to the value of V{} minus V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", x, y, x));
-
-                self.v[0xF] = if self.v[y as usize] > self.v[x] {
-                    1
-                } else {
-                    // No borrow occurred
-                    0
-                };
+            Instruction::Subn { x, y } => {
+                let x = x as usize;
+                debug!(self, "Set register V{} to the value of V{} minus V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", x, y, x);
 
-                self.v[x] = (self.v[y as usize] as u8).wrapping_sub(self.v[x]);
+                let (result, borrow) = self.v[y as usize].overflowing_sub(self.v[x]);
+                self.v[x] = result;
+                self.v[0xF] = !borrow as u8;
             }
-            (0x8, x, _, 0xE) => {
-                self.print_debug(format!("Shift the value of register V{} left one bit\n\tSet register VF to the most significant bit prior to the shift", x));
-
-                self.v[0xF] = self.v[x] >> 7;
-                self.v[x] <<= 1;
+            Instruction::Shl { x, y } => {
+                let x = x as usize;
+                let source = if self.quirks.shift_vx { x } else { y as usize };
+                debug!(self, "Store the value of register V{} shifted left one bit in register V{}\n\tSet register VF to the most significant bit prior to the shift", source, x);
+
+                let value = self.v[source];
+                self.v[x] = value << 1;
+                self.v[0xF] = value >> 7;
             }
-            (0x9, x, y, 0x0) => {
-                self.print_debug(format!("Skip the following instruction if V{} != V{}", x, y));
+            Instruction::SneVxVy { x, y } => {
+                let x = x as usize;
+                debug!(self, "Skip the following instruction if V{} != V{}", x, y);
 
                 if self.v[x] != self.v[y as usize] {
-                    self.pc += 2;
+                    self.skip_instruction();
                 }
             }
-            (0xA, _, _, _) => {
-                self.print_debug(format!("Store memory address {:#05X?} to register I", nnn));
+            Instruction::LdI(nnn) => {
+                debug!(self, "Store memory address {:#05X?} to register I", nnn);
 
                 self.i = nnn;
             }
-            (0xB, _, _, _) => {
-                self.print_debug(format!("Jump to address {} + V0", nnn));
+            Instruction::JpV0(nnn) => {
+                let x = (nnn >> 8) as usize;
+                let offset_register = if self.quirks.jump_vx { x } else { 0 };
+                debug!(self, "Jump to address {} + V{}", nnn, offset_register);
 
-                self.pc = nnn + (self.v[0] as u16);
+                self.pc = self.wrap_address(nnn + self.v[offset_register] as u16);
             }
-            (0xC, x, _, _) => {
-                self.print_debug(format!("Set V{} = random byte AND {:#04X?}", x, nn));
+            Instruction::Rnd { x, nn } => {
+                let x = x as usize;
+                debug!(self, "Set V{} = random byte AND {:#04X?}", x, nn);
 
-                let rnd = self.rng.gen::<u8>();
+                let rnd = self.rng.next_u8();
                 self.v[x] = rnd & nn;
             }
-            (0xD, x, y, n) => {
-                let y_coord = self.v[y as usize] as usize;
-                let x_coord = self.v[x] as usize;
-                self.print_debug(format!("Draw sprite {:?} at x={} y={}", n, x_coord, y_coord));
+            Instruction::Drw { x, y, n } => {
+                let x = x as usize;
+                let width = self.output_buffer.width();
+                let height = self.output_buffer.height();
+                // The starting position always wraps, the quirk only decides whether the rest of
+                // the sprite wraps as well or is clipped at the edges
+                let x_coord = self.v[x] as usize % width;
+                let y_coord = self.v[y as usize] as usize % height;
+
+                // SUPER-CHIP draws a 16x16 sprite made of two bytes per row for DXY0
+                let (sprite_width, rows) = if n == 0 && self.mode.supports_schip() {
+                    (16, 16)
+                } else {
+                    (8, n as usize)
+                };
+                debug!(self, "Draw {}x{} sprite at x={} y={}", sprite_width, rows, x_coord, y_coord);
 
-                let start_pos = (64 * y_coord) + x_coord;
                 let mut unset = false;
 
                 let mut draw = false;
-                for i in 0usize..(n as usize) {
-                    let line_data = self.memory[self.i as usize + i];
-
-                    let mut mask = 0x80u8;
-                    for j in 0..8 {
-                        let pixel_on = (line_data & mask) > 0;
-                        mask >>= 1;
-
-                        let current_pos = start_pos + (64 * i) + j;
-                        if current_pos > self.output_buffer.len() - 1 {
-                            continue;
-                        }
-                        if pixel_on {
-                            draw = true;
-                            if self.output_buffer[current_pos] == 0 {
-                                // self.output_buffer[current_pos] = from_u8_rgb(self.rng.gen::<u8>(), self.rng.gen::<u8>(), self.rng.gen::<u8>());
-                                self.output_buffer[current_pos] = from_u8_rgb(200, 200, 100);
-                            } else {
-                                self.output_buffer[current_pos] = 0;
-                                unset = true;
+                // XO-CHIP draws the sprite to every selected plane, reading the data for each
+                // plane right after the previous one
+                let mut sprite_address = self.i as usize;
+                let planes = self.planes;
+                let plane_count = [1, 2].iter().filter(|plane| planes & *plane != 0).count();
+                self.check_bounds(pc, sprite_address, plane_count * rows * sprite_width / 8)?;
+                for plane in [1, 2].iter().copied().filter(|plane| planes & plane != 0) {
+                    for i in 0usize..rows {
+                        let line_data = if sprite_width == 16 {
+                            let address = sprite_address + 2 * i;
+                            ((self.memory[address] as u16) << 8) | self.memory[address + 1] as u16
+                        } else {
+                            (self.memory[sprite_address + i] as u16) << 8
+                        };
+
+                        let mut mask = 0x8000u16;
+                        for j in 0..sprite_width {
+                            let pixel_on = (line_data & mask) > 0;
+                            mask >>= 1;
+
+                            let (mut pixel_x, mut pixel_y) = (x_coord + j, y_coord + i);
+                            if self.quirks.wrap_sprites {
+                                pixel_x %= width;
+                                pixel_y %= height;
+                            } else if pixel_x >= width || pixel_y >= height {
+                                continue;
+                            }
+                            if pixel_on {
+                                draw = true;
+                                unset |= self.output_buffer.toggle(pixel_x, pixel_y, plane);
                             }
                         }
                     }
+                    sprite_address += rows * sprite_width / 8;
                 }
 
                 self.v[0xF] = if unset {
@@ -406,48 +927,116 @@ impl CPU {
                     self.display.update_buffer(&self.output_buffer);
                 }
             }
-            (0xE, x, 0xA, 0x1) => {
-                self.print_debug(format!("Skips the next instruction if key {} isn't pressed.", self.v[x]));
+            Instruction::Skp(x) => {
+                let x = x as usize;
+                debug!(self, "Skips the next instruction if key {} is pressed.", self.v[x]);
+
+                if self.keyboard.is_key_pressed(self.v[x]) {
+                    debug!(self, "Key {} is pressed", self.v[x]);
+                    self.skip_instruction();
+                }
+            }
+            Instruction::Sknp(x) => {
+                let x = x as usize;
+                debug!(self, "Skips the next instruction if key {} isn't pressed.", self.v[x]);
 
                 if !self.keyboard.is_key_pressed(self.v[x]) {
-                    self.pc += 2
+                    self.skip_instruction();
                 } else {
-                    self.print_debug(format!("Key {} is pressed", self.v[x]))
+                    debug!(self, "Key {} is pressed", self.v[x]);
                 }
             }
-            (0xF, x, 0x0, 0x7) => {
-                self.print_debug(format!("Set V{} = delay timer", x));
+            Instruction::LdILong => {
+                self.check_bounds(pc, self.pc as usize, 2)?;
+                let address = ((self.memory[self.pc as usize] as u16) << 8) | self.memory[self.pc as usize + 1] as u16;
+                debug!(self, "Store memory address {:#06X?} to register I", address);
+
+                self.i = address;
+                self.pc = self.wrap_address(self.pc.wrapping_add(2));
+            }
+            Instruction::Plane(n) => {
+                debug!(self, "Select drawing planes {:#03b}", n);
+
+                self.planes = n & ALL_PLANES;
+            }
+            Instruction::Audio => {
+                debug!(self, "Load audio pattern from memory starting at location {:#06X?}.", self.i);
+
+                let start = self.i as usize;
+                self.check_bounds(pc, start, 16)?;
+                self.audio_pattern.copy_from_slice(&self.memory[start..start + 16]);
+                self.audio.set_pattern(&self.audio_pattern, self.pitch);
+            }
+            Instruction::LdVxDt(x) => {
+                let x = x as usize;
+                debug!(self, "Set V{} = delay timer", x);
 
                 self.v[x] = self.delay_timer;
             }
-            (0xF, x, 0x1, 0x5) => {
-                self.print_debug(format!("Set delay timer = V{}", x));
+            Instruction::LdVxK(x) => {
+                let x = x as usize;
+                debug!(self, "Wait for a key press and release, store the key in V{}", x);
+
+                // Like the COSMAC VIP, the key is only stored once it has been released again. Until
+                // then the instruction is repeated, so timers keep running while waiting.
+                match self.awaited_key {
+                    Some(key) if !self.keyboard.is_key_pressed(key) => {
+                        self.v[x] = key;
+                        self.awaited_key = None;
+                    }
+                    Some(_) => self.pc = pc,
+                    None => {
+                        self.awaited_key = (0..16).find(|key| self.keyboard.is_key_pressed(*key));
+                        self.pc = pc;
+                    }
+                }
+            }
+            Instruction::LdDtVx(x) => {
+                let x = x as usize;
+                debug!(self, "Set delay timer = V{}", x);
 
                 self.delay_timer = self.v[x];
             }
-            (0xF, x, 0x1, 0x8) => {
-                self.print_debug(format!("Set sound timer = V{}", x));
+            Instruction::LdStVx(x) => {
+                let x = x as usize;
+                debug!(self, "Set sound timer = V{}", x);
 
                 self.sound_timer = self.v[x];
+                self.update_tone();
+            }
+            Instruction::AddIVx(x) => {
+                let x = x as usize;
+                debug!(self, "Set I = I + V{}", x);
+
+                let sum = self.i as usize + self.v[x] as usize;
+                self.i = self.wrap_address(sum as u16);
+                // Only the Amiga interpreter reported running past the end of memory
+                if self.quirks.i_overflow {
+                    self.v[0xF] = (sum >= self.memory.len()) as u8;
+                }
             }
-            (0xF, x, 0x1, 0xE) => {
-                self.print_debug(format!("Set I = I + V{}", x));
+            Instruction::LdFVx(x) => {
+                let x = x as usize;
+                debug!(self, "Set I = location of sprite for digit V{}.", x);
 
-                self.i += self.v[x] as u16;
+                self.i = (self.v[x] as u16) * 0x5;
+            }
+            Instruction::LdHfVx(x) => {
+                let x = x as usize;
+                debug!(self, "Set I = location of large sprite for digit V{}.", x);
 
-                self.v[0xF] = if self.i > 256 {
-                    1
-                } else {
-                    0
-                };
+                self.i = (LARGE_FONT_ADDRESS + (self.v[x] as usize & 0xF) * 10) as u16;
             }
-            (0xF, x, 0x2, 0x9) => {
-                self.print_debug(format!("Set I = location of sprite for digit V{}.", x));
+            Instruction::Pitch(x) => {
+                let x = x as usize;
+                debug!(self, "Set audio pitch = V{}", x);
 
-                self.i = (self.v[x] as u16) * 0x5;
+                self.pitch = self.v[x];
+                self.audio.set_pattern(&self.audio_pattern, self.pitch);
             }
-            (0xF, x, 0x3, 0x3) => {
-                self.print_debug(format!("Store BCD representation of V{} in memory locations {:#06X?}, {:#06X?}, and {:#06X?}.", x, self.i, self.i + 1, self.i + 2));
+            Instruction::LdBVx(x) => {
+                let x = x as usize;
+                debug!(self, "Store BCD representation of V{} in memory locations {:#06X?}, {:#06X?}, and {:#06X?}.", x, self.i, self.i.wrapping_add(1), self.i.wrapping_add(2));
 
                 let num = self.v[x];
 
@@ -456,75 +1045,62 @@ impl CPU {
                 let ones = num % 10;
 
                 let start_address = self.i as usize;
+                self.check_bounds(pc, start_address, 3)?;
 
                 self.memory[start_address] = hundreds;
                 self.memory[start_address + 1] = tens;
                 self.memory[start_address + 2] = ones;
             }
-            (0xF, x, 0x5, 0x5) => {
-                self.print_debug(format!("Store registers V0 through V{} in memory starting at location {:#06X?}.", x, self.i));
+            Instruction::LdIVx(x) => {
+                let x = x as usize;
+                debug!(self, "Store registers V0 through V{} in memory starting at location {:#06X?}.", x, self.i);
 
+                self.check_bounds(pc, self.i as usize, x + 1)?;
                 for reg in 0..=x {
                     self.memory[self.i as usize + reg] = self.v[reg];
                 }
 
-                self.i += x as u16 + 1;
+                if self.quirks.increment_i {
+                    self.i = self.wrap_address(self.i.wrapping_add(x as u16 + 1));
+                }
             }
-            (0xF, x, 0x6, 0x5) => {
-                self.print_debug(format!("Read registers V0 through V{} from memory starting at location {:#06X?}.", x, self.i));
+            Instruction::LdVxI(x) => {
+                let x = x as usize;
+                debug!(self, "Read registers V0 through V{} from memory starting at location {:#06X?}.", x, self.i);
 
+                self.check_bounds(pc, self.i as usize, x + 1)?;
                 for reg in 0..=x {
                     self.v[reg] = self.memory[(self.i as usize) + reg];
                 }
 
-                self.i += x as u16 + 1;
+                if self.quirks.increment_i {
+                    self.i = self.wrap_address(self.i.wrapping_add(x as u16 + 1));
+                }
             }
-            (_, _, _, _) => panic!("UNRECOGNIZED OP: {:#06X?}", opcode)
+            Instruction::LdRVx(x) => {
+                let x = x as usize;
+                debug!(self, "Store registers V0 through V{} in the RPL user flags.", x);
+
+                self.rpl_flags[..=x].copy_from_slice(&self.v[..=x]);
+            }
+            Instruction::LdVxR(x) => {
+                let x = x as usize;
+                debug!(self, "Read registers V0 through V{} from the RPL user flags.", x);
+
+                self.v[..=x].copy_from_slice(&self.rpl_flags[..=x]);
+            }
+            Instruction::Unknown(_) => return Err(ChipError::UnknownOpcode { pc, opcode }),
         }
+
+        Ok(())
     }
+}
 
-//     pub fn disassemble_op(&mut self, opcode: u16) {
-//         let op1 = ((opcode & 0xF000) >> 12) as u8;
-//         let op2 = ((opcode & 0x0F00) >> 8) as u8;
-//         let op3 = ((opcode & 0x00F0) >> 4) as u8;
-//         let op4 = (opcode & 0x000F) as u8;
-//
-//         let nnn = opcode & 0x0FFF;
-//         let nn = (opcode & 0x00FF) as u8;
-//
-//         print!("{:#06X?}: ", opcode);
-//
-//         match (op1, op2, op3, op4) {
-//             (0x0, 0x0, 0xE, 0x0) => println!("Clear screen"),
-//             (0x0, 0x0, 0xE, 0xE) => println!("Return from a subroutine"),
-//             (0x0, _, _, _) => println!("Execute machine language subroutine at address {:#05X?}", nnn),
-//             (0x1, _, _, _) => println!("Jump to address {}", nnn),
-//             (0x2, _, _, _) => println!("Execute subroutine at address {:#05X?}", nnn),
-//             (0x3, x, _, _) => println!("Skip the following instruction if V{} == {}", x, nn),
-//             (0x4, x, _, _) => println!("Skip the following instruction if V{} != {}", x, nn),
-//             (0x5, x, y, 0) => println!("Skip the following instruction if V{} == V{}", x, y),
-//             (0x6, x, _, _) => { println!("Store {} in register V{}", nn, x) }
-//             (0x7, x, _, _) => println!("Add {} to register V{}", nn, x),
-//             (0x8, x, y, 0x0) => println!("Store V{} in V{}", y, x),
-//             (0x8, x, y, 0x1) => println!("Set V{} to V{} | V{}", x, x, y),
-//             (0x8, x, y, 0x2) => println!("Set V{} to V{} & V{}", x, x, y),
-//             (0x8, x, y, 0x3) => println!("Set V{} to V{} ^ V{}", x, x, y),
-//             (0x8, x, y, 0x4) => println!("Add the value of register V{} to register V{}\n\tSet VF to 01 if a carry occurs\n\tSet VF to 00 if a carry does not occur", y, x),
-//             (0x8, x, y, 0x5) => println!("Subtract the value of register V{} from register V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", y, x),
-//             (0x8, x, y, 0x6) => println!("Store the value of register V{} shifted right one bit in register V{}\n\tSet register VF to the least significant bit prior to the shift", y, x),
-//             (0x8, x, y, 0x7) => println!("Set register V{} to the value of V{} minus V{}\n\tSet VF to 00 if a borrow occurs\n\tSet VF to 01 if a borrow does not occur", x, y, x),
-//             (0x8, x, y, 0xE) => println!("Store the value of register V{} shifted left one bit in register V{}\n\tSet register VF to the most significant bit prior to the shift", y, x),
-//             (0x9, x, y, 0x0) => println!("Skip the following instruction if V{} != V{}", x, y),
-//             (0xA, _, _, _) => println!("Store memory address {:#05X?} to register I", nnn),
-//             (0xB, _, _, _) => println!("Jump to address {} + V0", nnn),
-//             (0xC, x, _, _) => println!("Set V{} = random byte AND {:#04X?}", x, nn),
-//             (0xD, x, y, n) => println!("Draw sprite {:?} at x={} y={}", n, x, y),
-//             (0xF, x, 0x0, 0x7) => println!("Set V{} = delay timer", x),
-//             (0xF, x, 0x1, 0x5) => println!("Set delay timer = V{}", x),
-//             (0xF, x, 0x2, 0x9) => println!("Set I = location of sprite for digit V{}.", x),
-//             (0xF, x, 0x3, 0x3) => println!("Store BCD representation of V{} in memory locations {:#06X?}, {:#06X?}, and {:#06X?}.", x, self.i, self.i + 1, self.i + 2),
-//             (0xF, x, 0x6, 0x5) => println!("Read registers V0 through V{} from memory starting at location {:#06X?}.", x, self.i),
-//             (_, _, _, _) => println!("UNRECOGNIZED OP: {:#06X?}", opcode)
-//         }
-//     }
+/// Registers from VX to VY, counting down if Y is lower than X.
+fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item=usize>> {
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
+    }
 }
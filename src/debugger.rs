@@ -0,0 +1,409 @@
+//! An interactive debugger for stepping through programs, driven by text commands.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use crate::audio::Audio;
+use crate::cpu::CPU;
+use crate::display::Display;
+use crate::dump;
+use crate::error::ChipError;
+use crate::instruction::{decode_for, Instruction};
+use crate::keyboard::MemoryKeyboard;
+
+pub const HELP: &str = "\
+Commands:
+  s, step [N]           Execute N instructions (default: 1)
+  c, continue [N]       Run until a breakpoint or watchpoint is hit, the program stops or N
+                        instructions have run (default: 1000000)
+  b, break <ADDR>       Stop when the program counter reaches ADDR
+  w, watch <TARGET>     Stop when TARGET changes, a register V0-VF, I or a memory address
+  delete <TARGET>       Remove the breakpoint or watchpoint on TARGET
+  l, list               List the breakpoints and watchpoints
+  r, regs               Print the registers, timers and program counter
+  stack                 Print the return addresses on the stack
+  x <ADDR> [LEN]        Dump LEN bytes of memory starting at ADDR (default: 64)
+  d, disasm [ADDR]      Disassemble the instructions around ADDR (default: PC)
+  screen                Print the screen
+  press <KEY>           Hold down the key 0-F
+  release <KEY>         Release the key 0-F
+  h, help               Print this message
+  q, quit               Leave the debugger
+
+An empty line repeats the previous command. Addresses are hex.";
+
+/// Instructions run by `continue` before giving control back, as programs waiting on the delay
+/// timer or polling keys would otherwise never stop.
+pub const CONTINUE_LIMIT: usize = 1_000_000;
+
+/// Instructions shown before and after the address by the `disasm` command.
+const DISASSEMBLY_CONTEXT: u16 = 4;
+
+/// A register or memory location the debugger can watch for changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Watch {
+    Register(u8),
+    I,
+    Memory(u16),
+}
+
+impl Watch {
+    fn value<D: Display, A: Audio>(self, cpu: &CPU<MemoryKeyboard, D, A>) -> u16 {
+        match self {
+            Watch::Register(x) => cpu.registers()[x as usize] as u16,
+            Watch::I => cpu.i(),
+            Watch::Memory(address) => cpu.memory().get(address as usize).copied().unwrap_or(0) as u16,
+        }
+    }
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Watch::Register(x) => write!(f, "V{:X}", x),
+            Watch::I => write!(f, "I"),
+            Watch::Memory(address) => write!(f, "memory at {:#05X}", address),
+        }
+    }
+}
+
+/// Parses `V0` to `VF`, `I` or a hex memory address.
+impl FromStr for Watch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Watch, String> {
+        let upper = s.to_uppercase();
+        if upper == "I" {
+            return Ok(Watch::I);
+        }
+        if let Some(register) = upper.strip_prefix('V') {
+            if let Ok(x) = u8::from_str_radix(register, 16) {
+                if x < 16 {
+                    return Ok(Watch::Register(x));
+                }
+            }
+        }
+        parse_address(s).map(Watch::Memory)
+    }
+}
+
+/// Why the debugger stopped running the program.
+#[derive(Debug)]
+pub enum Stop {
+    /// All the requested instructions were executed.
+    Stepped,
+    Breakpoint(u16),
+    Watchpoint { watch: Watch, old: u16, new: u16 },
+    /// The instruction at the address repeats itself, because it jumps to itself or waits for a key.
+    Waiting(u16),
+    /// Continuing ran the given number of instructions without stopping otherwise.
+    Limit(usize),
+    Exited,
+    Error(ChipError),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stop::Stepped => Ok(()),
+            Stop::Breakpoint(address) => write!(f, "breakpoint at {:#05X}", address),
+            Stop::Watchpoint { watch, old, new } => write!(f, "{} changed from {:#04X} to {:#04X}", watch, old, new),
+            Stop::Waiting(address) => write!(f, "program is waiting at {:#05X}", address),
+            Stop::Limit(count) => write!(f, "still running after {} instructions", count),
+            Stop::Exited => write!(f, "program exited"),
+            Stop::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+/// Breakpoints and watchpoints on a CPU, plus the state of the command line.
+///
+/// The CPU is driven with a [`MemoryKeyboard`], so keys can be pressed from the command line.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: BTreeSet<Watch>,
+    last_command: String,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger::default()
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    /// Removes a breakpoint, returning whether there was one at `address`.
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn add_watchpoint(&mut self, watch: Watch) {
+        self.watchpoints.insert(watch);
+    }
+
+    /// Removes a watchpoint, returning whether `watch` was watched.
+    pub fn remove_watchpoint(&mut self, watch: Watch) -> bool {
+        self.watchpoints.remove(&watch)
+    }
+
+    /// Executes up to `count` instructions, stopping early at breakpoints and watchpoints.
+    pub fn step<D: Display, A: Audio>(&self, cpu: &mut CPU<MemoryKeyboard, D, A>, count: usize) -> Stop {
+        self.run(cpu, count, false)
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program exits, fails or stops making
+    /// progress, or [`CONTINUE_LIMIT`] instructions have run.
+    pub fn resume<D: Display, A: Audio>(&self, cpu: &mut CPU<MemoryKeyboard, D, A>) -> Stop {
+        self.resume_for(cpu, CONTINUE_LIMIT)
+    }
+
+    /// Like [`resume`](Debugger::resume), but gives up after `limit` instructions.
+    pub fn resume_for<D: Display, A: Audio>(&self, cpu: &mut CPU<MemoryKeyboard, D, A>, limit: usize) -> Stop {
+        self.run(cpu, limit, true)
+    }
+
+    fn run<D: Display, A: Audio>(&self, cpu: &mut CPU<MemoryKeyboard, D, A>, count: usize, resuming: bool) -> Stop {
+        let mut executed = 0;
+        loop {
+            if cpu.has_exited() {
+                return Stop::Exited;
+            }
+            if executed == count {
+                return if resuming { Stop::Limit(count) } else { Stop::Stepped };
+            }
+            // The breakpoint execution resumes from doesn't stop it again
+            if executed > 0 && self.breakpoints.contains(&cpu.pc()) {
+                return Stop::Breakpoint(cpu.pc());
+            }
+
+            let pc = cpu.pc();
+            let before: Vec<u16> = self.watchpoints.iter().map(|watch| watch.value(cpu)).collect();
+            if let Err(e) = cpu.step() {
+                return Stop::Error(e);
+            }
+            executed += 1;
+
+            for (watch, old) in self.watchpoints.iter().zip(before) {
+                let new = watch.value(cpu);
+                if new != old {
+                    return Stop::Watchpoint { watch: *watch, old, new };
+                }
+            }
+            if resuming && cpu.pc() == pc {
+                return Stop::Waiting(pc);
+            }
+        }
+    }
+
+    /// Executes a command line, returning its output or `None` if the command was `quit`.
+    pub fn execute<D: Display, A: Audio>(&mut self, cpu: &mut CPU<MemoryKeyboard, D, A>, line: &str) -> Option<String> {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_string(),
+        };
+        self.last_command = line.clone();
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Some(String::new()),
+        };
+
+        let output = match command {
+            "q" | "quit" => return None,
+            "s" | "step" => parse_count(args.first(), 1).map(|count| {
+                let stop = self.step(cpu, count);
+                self.stopped(cpu, stop)
+            }),
+            "c" | "continue" => parse_count(args.first(), CONTINUE_LIMIT).map(|limit| {
+                let stop = self.resume_for(cpu, limit);
+                self.stopped(cpu, stop)
+            }),
+            "b" | "break" => required(args.first()).and_then(parse_address).map(|address| {
+                self.add_breakpoint(address);
+                format!("breakpoint at {:#05X}", address)
+            }),
+            "w" | "watch" => required(args.first()).and_then(|arg| arg.parse::<Watch>()).map(|watch| {
+                self.add_watchpoint(watch);
+                format!("watching {}", watch)
+            }),
+            "delete" => required(args.first()).and_then(|arg| {
+                let watch: Watch = arg.parse()?;
+                let removed = match watch {
+                    Watch::Memory(address) => self.remove_breakpoint(address) | self.remove_watchpoint(watch),
+                    _ => self.remove_watchpoint(watch),
+                };
+                if removed {
+                    Ok(format!("deleted {}", arg))
+                } else {
+                    Err(format!("no breakpoint or watchpoint on {}", arg))
+                }
+            }),
+            "l" | "list" => Ok(self.list()),
+            "r" | "regs" => Ok(registers(cpu)),
+            "stack" => Ok(stack(cpu)),
+            "x" => required(args.first()).and_then(parse_address).and_then(|address| {
+                let len = parse_count(args.get(1), 64)?;
+                Ok(hex_dump(cpu.memory(), address as usize, len))
+            }),
+            "d" | "disasm" => match args.first() {
+                Some(arg) => parse_address(arg),
+                None => Ok(cpu.pc()),
+            }.map(|address| self.disassemble(cpu, address, DISASSEMBLY_CONTEXT, DISASSEMBLY_CONTEXT)),
+            "screen" => Ok(dump::to_ascii(cpu.framebuffer()).trim_end().to_string()),
+            "press" | "release" => required(args.first()).and_then(parse_key).map(|key| {
+                if command == "press" {
+                    cpu.keyboard_mut().press(key);
+                    format!("key {:X} pressed", key)
+                } else {
+                    cpu.keyboard_mut().release(key);
+                    format!("key {:X} released", key)
+                }
+            }),
+            "h" | "help" => Ok(String::from(HELP)),
+            _ => Err(format!("unknown command '{}', try help", command)),
+        };
+
+        Some(output.unwrap_or_else(|e| format!("error: {}", e)))
+    }
+
+    /// Reads commands from `input` until `quit` or the end of the input, writing a prompt and the
+    /// output of every command to `output`.
+    pub fn repl<D: Display, A: Audio, R: BufRead, W: Write>(&mut self, cpu: &mut CPU<MemoryKeyboard, D, A>, input: R, mut output: W) -> io::Result<()> {
+        writeln!(output, "{}", self.disassemble(cpu, cpu.pc(), 0, 0))?;
+
+        let mut lines = input.lines();
+        loop {
+            write!(output, "(chipper) ")?;
+            output.flush()?;
+
+            let line = match lines.next() {
+                Some(line) => line?,
+                // Ends the prompt's line
+                None => return writeln!(output),
+            };
+            match self.execute(cpu, &line) {
+                Some(text) if text.is_empty() => {}
+                Some(text) => writeln!(output, "{}", text)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Describes why execution stopped, followed by the next instruction.
+    fn stopped<D: Display, A: Audio>(&self, cpu: &CPU<MemoryKeyboard, D, A>, stop: Stop) -> String {
+        let next = self.disassemble(cpu, cpu.pc(), 0, 0);
+        match stop {
+            Stop::Stepped => next,
+            stop => format!("{}\n{}", stop, next),
+        }
+    }
+
+    fn list(&self) -> String {
+        let mut lines: Vec<String> = self.breakpoints.iter()
+            .map(|address| format!("breakpoint at {:#05X}", address))
+            .collect();
+        lines.extend(self.watchpoints.iter().map(|watch| format!("watching {}", watch)));
+        if lines.is_empty() {
+            return String::from("no breakpoints or watchpoints");
+        }
+        lines.join("\n")
+    }
+
+    /// Disassembles the instructions from `before` instructions before `address` to `after`
+    /// instructions after it, marking the program counter with `>` and breakpoints with `*`.
+    ///
+    /// Instructions are decoded one after the other, so data or misaligned addresses show up as
+    /// nonsense instructions.
+    fn disassemble<D: Display, A: Audio>(&self, cpu: &CPU<MemoryKeyboard, D, A>, address: u16, before: u16, after: u16) -> String {
+        let memory = cpu.memory();
+        let mut address = address.saturating_sub(2 * before) as usize;
+        let mut lines = Vec::new();
+        for _ in 0..=before + after {
+            if address + 1 >= memory.len() {
+                break;
+            }
+            let opcode = (memory[address] as u16) << 8 | memory[address + 1] as u16;
+            let instruction = decode_for(opcode, cpu.mode());
+            let text = match instruction {
+                Instruction::LdILong if address + 3 < memory.len() => {
+                    format!("LD I, LONG {:#06X}", (memory[address + 2] as u16) << 8 | memory[address + 3] as u16)
+                }
+                instruction => instruction.to_string(),
+            };
+
+            let pc_marker = if address == cpu.pc() as usize { '>' } else { ' ' };
+            let breakpoint_marker = if self.breakpoints.contains(&(address as u16)) { '*' } else { ' ' };
+            lines.push(format!("{}{} {:03X}  {:04X}  {}", pc_marker, breakpoint_marker, address, opcode, text));
+            address += instruction.size() as usize;
+        }
+        lines.join("\n")
+    }
+}
+
+fn registers<D: Display, A: Audio>(cpu: &CPU<MemoryKeyboard, D, A>) -> String {
+    let mut text = format!("PC {:#05X}  I {:#05X}  SP {}  DT {}  ST {}",
+                           cpu.pc(), cpu.i(), cpu.stack().len(), cpu.delay_timer(), cpu.sound_timer());
+    for (row, values) in cpu.registers().chunks(8).enumerate() {
+        let registers: Vec<String> = values.iter().enumerate()
+            .map(|(x, value)| format!("V{:X} {:02X}", row * 8 + x, value))
+            .collect();
+        text.push('\n');
+        text.push_str(&registers.join("  "));
+    }
+    text
+}
+
+fn stack<D: Display, A: Audio>(cpu: &CPU<MemoryKeyboard, D, A>) -> String {
+    if cpu.stack().is_empty() {
+        return String::from("stack is empty");
+    }
+    let lines: Vec<String> = cpu.stack().iter().enumerate().rev()
+        .map(|(level, address)| format!("{:2}  {:#05X}", level, address))
+        .collect();
+    lines.join("\n")
+}
+
+/// Formats `len` bytes starting at `address` 16 to a line, cut off at the end of memory.
+fn hex_dump(memory: &[u8], address: usize, len: usize) -> String {
+    let end = address.saturating_add(len).min(memory.len());
+    if address >= end {
+        return format!("{:#05X} is past the end of memory", address);
+    }
+    let lines: Vec<String> = memory[address..end].chunks(16).enumerate()
+        .map(|(row, bytes)| {
+            let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            format!("{:03X}  {}", address + row * 16, bytes.join(" "))
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn required<'a>(arg: Option<&&'a str>) -> Result<&'a str, String> {
+    arg.copied().ok_or_else(|| String::from("missing argument, try help"))
+}
+
+fn parse_address(value: &str) -> Result<u16, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address '{}', expected a hex value", value))
+}
+
+fn parse_count(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    match arg {
+        Some(value) => value.parse().map_err(|_| format!("invalid count '{}'", value)),
+        None => Ok(default),
+    }
+}
+
+fn parse_key(value: &str) -> Result<u8, String> {
+    match u8::from_str_radix(value, 16) {
+        Ok(key) if key < 16 => Ok(key),
+        _ => Err(format!("invalid key '{}', expected a hex digit", value)),
+    }
+}
@@ -0,0 +1,171 @@
+//! Disassembly of whole programs, following the control flow from the entry point to tell code
+//! apart from data.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::cpu::Mode;
+use crate::instruction::{decode_for, Instruction};
+
+/// Address programs are loaded at and start running from.
+pub const START_ADDRESS: u16 = 0x200;
+
+/// Most data bytes listed per `DB` line.
+const BYTES_PER_LINE: usize = 8;
+
+/// A program split into instructions reachable from [`START_ADDRESS`] and data.
+///
+/// Formatting it gives a listing in Cowgod's syntax, with labels for jump and call targets and
+/// the address and raw bytes of every line in a trailing comment, e.g.
+///
+/// ```text
+/// loc_200:
+///     LD VA, 0x02                                       ; 200  6A 02
+/// ```
+pub struct Disassembly {
+    program: Vec<u8>,
+    code: BTreeMap<u16, Instruction>,
+    labels: BTreeMap<u16, String>,
+}
+
+/// Disassembles a program loaded at [`START_ADDRESS`], decoding instructions as a CPU in `mode`
+/// does.
+///
+/// Instructions are found by following every path the program can take: jumps, calls and both
+/// outcomes of skips. `BNNN` is followed to its base address, as it usually jumps into a table of
+/// jumps there. Everything that is never reached is listed as data.
+pub fn disassemble(program: &[u8], mode: Mode) -> Disassembly {
+    let mut code = BTreeMap::new();
+    let mut labels = BTreeMap::new();
+    let mut covered = vec![false; program.len()];
+    let mut pending = vec![START_ADDRESS];
+
+    let opcode_at = |address: u16| -> Option<u16> {
+        let offset = (address as usize).checked_sub(START_ADDRESS as usize)?;
+        let bytes = program.get(offset..offset + 2)?;
+        Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+    };
+
+    while let Some(address) = pending.pop() {
+        if code.contains_key(&address) {
+            continue;
+        }
+        let instruction = match opcode_at(address).map(|opcode| decode_for(opcode, mode)) {
+            Some(Instruction::Unknown(_)) | None => continue,
+            Some(instruction) => instruction,
+        };
+        let offset = (address - START_ADDRESS) as usize;
+        let size = instruction.size() as usize;
+        // Skip instructions running past the end or overlapping one found before, which happens
+        // when data is jumped over without being reached
+        match covered.get(offset..offset + size) {
+            Some(bytes) if bytes.iter().all(|covered| !covered) => {}
+            _ => continue,
+        }
+        covered[offset..offset + size].iter_mut().for_each(|covered| *covered = true);
+        code.insert(address, instruction);
+
+        let next = address.wrapping_add(size as u16);
+        match instruction {
+            Instruction::Jp(target) | Instruction::JpV0(target) => {
+                labels.entry(target).or_insert_with(|| format!("loc_{:03X}", target));
+                pending.push(target);
+            }
+            Instruction::Call(target) => {
+                labels.insert(target, format!("sub_{:03X}", target));
+                pending.push(target);
+                pending.push(next);
+            }
+            Instruction::Ret | Instruction::Exit => {}
+            Instruction::SeVxByte { .. } | Instruction::SneVxByte { .. } | Instruction::SeVxVy { .. }
+            | Instruction::SneVxVy { .. } | Instruction::Skp(_) | Instruction::Sknp(_) => {
+                let skipped = opcode_at(next).map_or(2, |opcode| decode_for(opcode, mode).size());
+                pending.push(next);
+                pending.push(next.wrapping_add(skipped));
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    // Targets outside of the program or in the middle of an instruction can't be labelled
+    let end = START_ADDRESS as usize + program.len();
+    labels.retain(|address, _| {
+        let in_program = (START_ADDRESS as usize..end).contains(&(*address as usize));
+        in_program && (code.contains_key(address) || !covered[(*address - START_ADDRESS) as usize])
+    });
+    labels.entry(START_ADDRESS).or_insert_with(|| format!("loc_{:03X}", START_ADDRESS));
+
+    Disassembly {
+        program: program.to_vec(),
+        code,
+        labels,
+    }
+}
+
+impl Disassembly {
+    /// Instructions reachable from the entry point, by address.
+    pub fn code(&self) -> &BTreeMap<u16, Instruction> {
+        &self.code
+    }
+
+    /// Names of the jump and call targets, by address.
+    pub fn labels(&self) -> &BTreeMap<u16, String> {
+        &self.labels
+    }
+
+    /// Formats the instruction at `address`, naming its target if it has a label.
+    fn instruction_text(&self, address: u16, instruction: Instruction) -> String {
+        let target = |address: u16| match self.labels.get(&address) {
+            Some(label) => label.clone(),
+            None => format!("{:#05X}", address),
+        };
+        match instruction {
+            Instruction::Jp(address) => format!("JP {}", target(address)),
+            Instruction::Call(address) => format!("CALL {}", target(address)),
+            Instruction::JpV0(address) => format!("JP V0, {}", target(address)),
+            Instruction::LdI(address) => format!("LD I, {}", target(address)),
+            Instruction::LdILong => {
+                let offset = (address - START_ADDRESS) as usize + 2;
+                let address = (self.program[offset] as u16) << 8 | self.program[offset + 1] as u16;
+                format!("LD I, LONG {:#06X}", address)
+            }
+            _ => instruction.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut offset = 0;
+        while offset < self.program.len() {
+            let address = START_ADDRESS + offset as u16;
+            if let Some(label) = self.labels.get(&address) {
+                writeln!(f, "{}:", label)?;
+            }
+
+            let (text, size) = match self.code.get(&address) {
+                Some(instruction) => (self.instruction_text(address, *instruction), instruction.size() as usize),
+                None => {
+                    // Data runs up to the next instruction or label
+                    let size = (1..BYTES_PER_LINE.min(self.program.len() - offset))
+                        .find(|size| {
+                            let next = address + *size as u16;
+                            self.code.contains_key(&next) || self.labels.contains_key(&next)
+                        })
+                        .unwrap_or_else(|| BYTES_PER_LINE.min(self.program.len() - offset));
+                    let bytes: Vec<String> = self.program[offset..offset + size].iter()
+                        .map(|byte| format!("{:#04X}", byte))
+                        .collect();
+                    (format!("DB {}", bytes.join(", ")), size)
+                }
+            };
+
+            let raw: Vec<String> = self.program[offset..offset + size].iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            writeln!(f, "    {:<50}; {:03X}  {}", text, address, raw.join(" "))?;
+            offset += size;
+        }
+        Ok(())
+    }
+}
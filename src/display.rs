@@ -1,38 +1,53 @@
-use minifb::Window;
-use std::rc::Rc;
-use std::cell::RefCell;
-use crate::SCALE_FACTOR;
-
-pub struct Display {
-    pub(crate) window: Rc<RefCell<Window>>,
-    height: usize,
-    width: usize,
-    buffer: Vec<u32>,
+use crate::framebuffer::Framebuffer;
+
+/// Width of the CHIP-8 screen in pixels.
+pub const WIDTH: usize = 64;
+/// Height of the CHIP-8 screen in pixels.
+pub const HEIGHT: usize = 32;
+/// Width of the SUPER-CHIP high resolution screen in pixels.
+pub const HIRES_WIDTH: usize = 128;
+/// Height of the SUPER-CHIP high resolution screen in pixels.
+pub const HIRES_HEIGHT: usize = 64;
+
+/// Receives the framebuffer from the [`CPU`](crate::CPU) whenever the screen changes.
+pub trait Display {
+    fn update_buffer(&mut self, buf: &Framebuffer);
+}
+
+/// A [`Display`] that keeps the most recent framebuffer in memory, for running without a window.
+pub struct MemoryDisplay {
+    buffer: Framebuffer,
+    updates: usize,
 }
 
-impl Display {
-    pub fn new(window: Rc<RefCell<Window>>) -> Display {
-        let (width, height) = (*window).borrow().get_size();
-        Display {
-            window,
-            height,
-            width,
-            buffer: vec![0; width * height],
+impl MemoryDisplay {
+    pub fn new() -> MemoryDisplay {
+        MemoryDisplay {
+            buffer: Framebuffer::new(WIDTH, HEIGHT),
+            updates: 0,
         }
     }
 
-    pub fn update_buffer(&mut self, buf: &[u32; 2048]) {
-        for (i, val) in buf.iter().enumerate() {
-            let y = i / (self.width / SCALE_FACTOR);
-            let x = i - (self.width / SCALE_FACTOR * y);
-            for x_coord in (x * SCALE_FACTOR)..((x * SCALE_FACTOR) + SCALE_FACTOR) {
-                for y_coord in (y * SCALE_FACTOR)..((y * SCALE_FACTOR) + SCALE_FACTOR) {
-                    self.buffer[(y_coord * self.width) + x_coord] = *val;
-                }
-            }
-        }
+    /// The last framebuffer received from the CPU.
+    pub fn buffer(&self) -> &Framebuffer {
+        &self.buffer
+    }
+
+    /// Number of times the CPU has updated the screen.
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+}
+
+impl Default for MemoryDisplay {
+    fn default() -> MemoryDisplay {
+        MemoryDisplay::new()
+    }
+}
 
-        (*self.window).borrow_mut().update_with_buffer(&self.buffer, self.width, self.height)
-            .unwrap();
+impl Display for MemoryDisplay {
+    fn update_buffer(&mut self, buf: &Framebuffer) {
+        self.buffer.clone_from(buf);
+        self.updates += 1;
     }
 }
@@ -0,0 +1,108 @@
+//! Writers for saving a framebuffer to disk, mostly useful when running headless.
+
+use std::io;
+use std::io::prelude::*;
+
+use crate::framebuffer::Framebuffer;
+use crate::palette::Palette;
+
+/// Writes the framebuffer as a plain (ASCII) PBM image, pixels set in any plane are black.
+pub fn write_pbm<W: Write>(out: &mut W, buf: &Framebuffer) -> io::Result<()> {
+    writeln!(out, "P1")?;
+    writeln!(out, "{} {}", buf.width(), buf.height())?;
+    for row in buf.rows() {
+        let line: Vec<&str> = row
+            .map(|val| if val == 0 { "0" } else { "1" })
+            .collect();
+        writeln!(out, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Renders the framebuffer as text, one line per row. Unset pixels are `.` and set pixels `#`,
+/// or `+` and `@` for pixels set in the second XO-CHIP plane and in both planes.
+pub fn to_ascii(buf: &Framebuffer) -> String {
+    let mut text = String::with_capacity((buf.width() + 1) * buf.height());
+    for row in buf.rows() {
+        text.extend(row.map(|val| ['.', '#', '+', '@'][val as usize & 0b11]));
+        text.push('\n');
+    }
+    text
+}
+
+/// Writes the framebuffer as an RGB PNG, drawing each pixel in its `palette` colour.
+///
+/// The image data is stored uncompressed, which keeps the encoder trivial at the cost of a few
+/// kilobytes per image.
+pub fn write_png<W: Write>(out: &mut W, buf: &Framebuffer, palette: Palette) -> io::Result<()> {
+    let mut raw = Vec::with_capacity(buf.height() * (1 + buf.width() * 3));
+    for row in buf.rows() {
+        // Filter type 0 (None) for every scanline
+        raw.push(0);
+        for val in row {
+            let color = palette.color(val);
+            raw.extend_from_slice(&color.to_be_bytes()[1..]);
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(buf.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(buf.height() as u32).to_be_bytes());
+    // 8 bit depth, RGB colour, deflate, adaptive filtering, no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'])?;
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data.iter()));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xFFFF;
+
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32<'a, I: Iterator<Item=&'a u8>>(bytes: I) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
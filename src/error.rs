@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong loading or running a program.
+#[derive(Debug)]
+pub enum ChipError {
+    /// The ROM couldn't be read.
+    IoError(io::Error),
+    /// The ROM is larger than the memory available to programs.
+    RomTooLarge { size: usize, max: usize },
+    /// The instruction at `pc` isn't part of the instruction set of the current mode.
+    UnknownOpcode { pc: u16, opcode: u16 },
+    /// The subroutine call at `pc` exceeded the 16 levels of the stack.
+    StackOverflow { pc: u16 },
+    /// The instruction at `pc` returned from a subroutine with an empty stack.
+    StackUnderflow { pc: u16 },
+    /// The instruction at `pc` accessed memory past its end.
+    MemoryOutOfBounds { pc: u16, address: usize },
+    /// A save state couldn't be loaded as it's corrupted or from an incompatible version.
+    InvalidSaveState(String),
+    /// A movie couldn't be loaded as it's corrupted or from an incompatible version.
+    InvalidMovie(String),
+}
+
+pub type Result<T> = std::result::Result<T, ChipError>;
+
+impl fmt::Display for ChipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChipError::IoError(e) => write!(f, "{}", e),
+            ChipError::RomTooLarge { size, max } => write!(f, "ROM is {} bytes, at most {} bytes fit in memory", size, max),
+            ChipError::UnknownOpcode { pc, opcode } => write!(f, "unknown opcode {:#06X} at {:#06X}", opcode, pc),
+            ChipError::StackOverflow { pc } => write!(f, "stack overflow calling a subroutine at {:#06X}", pc),
+            ChipError::StackUnderflow { pc } => write!(f, "stack underflow returning from a subroutine at {:#06X}", pc),
+            ChipError::MemoryOutOfBounds { pc, address } => write!(f, "memory access out of bounds at {:#06X}, address {:#06X}", pc, address),
+            ChipError::InvalidSaveState(reason) => write!(f, "invalid save state: {}", reason),
+            ChipError::InvalidMovie(reason) => write!(f, "invalid movie: {}", reason),
+        }
+    }
+}
+
+impl Error for ChipError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ChipError::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ChipError {
+    fn from(e: io::Error) -> ChipError {
+        ChipError::IoError(e)
+    }
+}
@@ -0,0 +1,164 @@
+/// The pixels of the screen, stored as one bit per pixel and drawing plane.
+///
+/// A pixel's value is a mask of the planes it's set in, so `0` is an unset pixel and `1` a pixel
+/// set in the first plane, which is the only plane outside of XO-CHIP. XO-CHIP programs can also
+/// draw to the second plane, making `2` and `3` possible values. The framebuffer knows nothing
+/// about colours, those are picked by a [`Palette`](crate::Palette) when the screen is shown.
+///
+/// The resolution isn't fixed as SUPER-CHIP programs can switch between the 64x32 low resolution
+/// and the 128x64 high resolution mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    /// Words per row in each plane.
+    stride: usize,
+    /// Every plane row by row, with the leftmost pixel of a word in its lowest bit. Bits past the
+    /// width are always unset.
+    planes: [Vec<u64>; PLANES],
+}
+
+/// Number of drawing planes.
+pub const PLANES: usize = 2;
+
+/// Plane mask selecting every plane.
+pub const ALL_PLANES: u8 = 0b11;
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        let stride = width.div_ceil(64);
+        Framebuffer {
+            width,
+            height,
+            stride,
+            planes: [vec![0; stride * height], vec![0; stride * height]],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The planes the pixel at `x`, `y` is set in.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let (word, bit) = self.locate(x, y);
+        self.planes.iter().enumerate()
+            .filter(|(_, plane)| plane[word] & bit != 0)
+            .fold(0, |value, (index, _)| value | 1 << index)
+    }
+
+    /// Sets the pixel at `x`, `y` in exactly the planes of `value`.
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        let (word, bit) = self.locate(x, y);
+        for (index, plane) in self.planes.iter_mut().enumerate() {
+            if value & 1 << index != 0 {
+                plane[word] |= bit;
+            } else {
+                plane[word] &= !bit;
+            }
+        }
+    }
+
+    /// Flips the pixel at `x`, `y` in the planes selected by `planes`, returning whether it was
+    /// set in any of them, i.e. whether drawing it caused a collision.
+    pub fn toggle(&mut self, x: usize, y: usize, planes: u8) -> bool {
+        let (word, bit) = self.locate(x, y);
+        let mut erased = false;
+        for (index, plane) in self.planes.iter_mut().enumerate() {
+            if planes & 1 << index != 0 {
+                erased |= plane[word] & bit != 0;
+                plane[word] ^= bit;
+            }
+        }
+        erased
+    }
+
+    /// Every pixel's value, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item=u8> + '_ {
+        self.rows().flatten()
+    }
+
+    /// The pixel values of every row, from the top.
+    pub fn rows(&self) -> impl Iterator<Item=impl Iterator<Item=u8> + '_> + '_ {
+        (0..self.height).map(move |y| (0..self.width).map(move |x| self.pixel(x, y)))
+    }
+
+    /// Whether no pixel is set in any plane.
+    pub fn is_blank(&self) -> bool {
+        self.planes.iter().all(|plane| plane.iter().all(|word| *word == 0))
+    }
+
+    /// Unsets every pixel in the planes selected by the `planes` mask.
+    pub fn clear(&mut self, planes: u8) {
+        for plane in self.selected_mut(planes) {
+            plane.iter_mut().for_each(|word| *word = 0);
+        }
+    }
+
+    /// Changes the resolution, which clears the screen.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = Framebuffer::new(width, height);
+    }
+
+    /// Moves the planes selected by `planes` down by `lines`, leaving blank rows at the top.
+    pub fn scroll_down(&mut self, lines: usize, planes: u8) {
+        let shift = lines.min(self.height) * self.stride;
+        for plane in self.selected_mut(planes) {
+            plane.rotate_right(shift);
+            plane[..shift].iter_mut().for_each(|word| *word = 0);
+        }
+    }
+
+    /// Moves the planes selected by `planes` up by `lines`, leaving blank rows at the bottom.
+    pub fn scroll_up(&mut self, lines: usize, planes: u8) {
+        let shift = lines.min(self.height) * self.stride;
+        for plane in self.selected_mut(planes) {
+            plane.rotate_left(shift);
+            let len = plane.len();
+            plane[len - shift..].iter_mut().for_each(|word| *word = 0);
+        }
+    }
+
+    /// Moves the planes selected by `planes` left by `columns`, leaving blank columns on the right.
+    pub fn scroll_left(&mut self, columns: usize, planes: u8) {
+        let columns = columns.min(self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let source = if x + columns < self.width { self.pixel(x + columns, y) } else { 0 };
+                self.move_pixel(x, y, source, planes);
+            }
+        }
+    }
+
+    /// Moves the planes selected by `planes` right by `columns`, leaving blank columns on the left.
+    pub fn scroll_right(&mut self, columns: usize, planes: u8) {
+        let columns = columns.min(self.width);
+        for y in 0..self.height {
+            for x in (0..self.width).rev() {
+                let source = if x >= columns { self.pixel(x - columns, y) } else { 0 };
+                self.move_pixel(x, y, source, planes);
+            }
+        }
+    }
+
+    fn move_pixel(&mut self, x: usize, y: usize, source: u8, planes: u8) {
+        let pixel = self.pixel(x, y);
+        self.set_pixel(x, y, (pixel & !planes) | (source & planes));
+    }
+
+    /// The word holding the pixel at `x`, `y` in each plane and the pixel's bit in it.
+    fn locate(&self, x: usize, y: usize) -> (usize, u64) {
+        assert!(x < self.width && y < self.height, "pixel {},{} is off the screen", x, y);
+        (y * self.stride + x / 64, 1 << (x % 64))
+    }
+
+    fn selected_mut(&mut self, planes: u8) -> impl Iterator<Item=&mut Vec<u64>> {
+        self.planes.iter_mut().enumerate()
+            .filter(move |(index, _)| planes & 1 << index != 0)
+            .map(|(_, plane)| plane)
+    }
+}
@@ -0,0 +1,357 @@
+//! Decoding of opcodes into instructions, shared by the CPU and the tools working with ROMs.
+
+use std::fmt;
+
+use crate::cpu::Mode;
+
+/// A decoded instruction, named after the mnemonics of Cowgod's technical reference.
+///
+/// `x` and `y` are register numbers, `nn` an immediate byte and `addr` a 12-bit address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// `00CN`, SUPER-CHIP: scroll the screen down N lines.
+    Scd(u8),
+    /// `00DN`, XO-CHIP: scroll the screen up N lines.
+    Scu(u8),
+    /// `00E0`: clear the screen.
+    Cls,
+    /// `00EE`: return from a subroutine.
+    Ret,
+    /// `00FB`, SUPER-CHIP: scroll the screen right 4 pixels.
+    Scr,
+    /// `00FC`, SUPER-CHIP: scroll the screen left 4 pixels.
+    Scl,
+    /// `00FD`, SUPER-CHIP: exit the interpreter.
+    Exit,
+    /// `00FE`, SUPER-CHIP: switch to low resolution.
+    Low,
+    /// `00FF`, SUPER-CHIP: switch to high resolution.
+    High,
+    /// `0NNN`: call a machine language subroutine, which is ignored.
+    Sys(u16),
+    /// `1NNN`: jump to NNN.
+    Jp(u16),
+    /// `2NNN`: call the subroutine at NNN.
+    Call(u16),
+    /// `3XNN`: skip the next instruction if VX == NN.
+    SeVxByte { x: u8, nn: u8 },
+    /// `4XNN`: skip the next instruction if VX != NN.
+    SneVxByte { x: u8, nn: u8 },
+    /// `5XY0`: skip the next instruction if VX == VY.
+    SeVxVy { x: u8, y: u8 },
+    /// `5XY2`, XO-CHIP: store VX through VY in memory starting at I.
+    SaveRange { x: u8, y: u8 },
+    /// `5XY3`, XO-CHIP: read VX through VY from memory starting at I.
+    LoadRange { x: u8, y: u8 },
+    /// `6XNN`: VX = NN.
+    LdVxByte { x: u8, nn: u8 },
+    /// `7XNN`: VX += NN, without carry.
+    AddVxByte { x: u8, nn: u8 },
+    /// `8XY0`: VX = VY.
+    LdVxVy { x: u8, y: u8 },
+    /// `8XY1`: VX |= VY.
+    Or { x: u8, y: u8 },
+    /// `8XY2`: VX &= VY.
+    And { x: u8, y: u8 },
+    /// `8XY3`: VX ^= VY.
+    Xor { x: u8, y: u8 },
+    /// `8XY4`: VX += VY, VF = carry.
+    AddVxVy { x: u8, y: u8 },
+    /// `8XY5`: VX -= VY, VF = not borrow.
+    Sub { x: u8, y: u8 },
+    /// `8XY6`: VX = VY >> 1 (or VX >> 1), VF = the bit shifted out.
+    Shr { x: u8, y: u8 },
+    /// `8XY7`: VX = VY - VX, VF = not borrow.
+    Subn { x: u8, y: u8 },
+    /// `8XYE`: VX = VY << 1 (or VX << 1), VF = the bit shifted out.
+    Shl { x: u8, y: u8 },
+    /// `9XY0`: skip the next instruction if VX != VY.
+    SneVxVy { x: u8, y: u8 },
+    /// `ANNN`: I = NNN.
+    LdI(u16),
+    /// `BNNN`: jump to NNN + V0 (or NNN + VX with the `jump_vx` quirk).
+    JpV0(u16),
+    /// `CXNN`: VX = random byte & NN.
+    Rnd { x: u8, nn: u8 },
+    /// `DXYN`: draw an N rows high sprite at (VX, VY).
+    Drw { x: u8, y: u8, n: u8 },
+    /// `EX9E`: skip the next instruction if key VX is pressed.
+    Skp(u8),
+    /// `EXA1`: skip the next instruction if key VX isn't pressed.
+    Sknp(u8),
+    /// `F000 NNNN`, XO-CHIP: I = the 16-bit address in the next two bytes.
+    LdILong,
+    /// `FN01`, XO-CHIP: select the drawing planes N.
+    Plane(u8),
+    /// `F002`, XO-CHIP: load the 16 byte audio pattern at I.
+    Audio,
+    /// `FX07`: VX = delay timer.
+    LdVxDt(u8),
+    /// `FX0A`: wait for a key press and store the key in VX.
+    LdVxK(u8),
+    /// `FX15`: delay timer = VX.
+    LdDtVx(u8),
+    /// `FX18`: sound timer = VX.
+    LdStVx(u8),
+    /// `FX1E`: I += VX.
+    AddIVx(u8),
+    /// `FX29`: I = address of the small font sprite for digit VX.
+    LdFVx(u8),
+    /// `FX30`, SUPER-CHIP: I = address of the large font sprite for digit VX.
+    LdHfVx(u8),
+    /// `FX33`: store the BCD representation of VX at I, I + 1 and I + 2.
+    LdBVx(u8),
+    /// `FX3A`, XO-CHIP: audio pitch = VX.
+    Pitch(u8),
+    /// `FX55`: store V0 through VX in memory starting at I.
+    LdIVx(u8),
+    /// `FX65`: read V0 through VX from memory starting at I.
+    LdVxI(u8),
+    /// `FX75`, SUPER-CHIP: store V0 through VX in the RPL user flags.
+    LdRVx(u8),
+    /// `FX85`, SUPER-CHIP: read V0 through VX from the RPL user flags.
+    LdVxR(u8),
+    /// An opcode that isn't an instruction in any mode.
+    Unknown(u16),
+}
+
+/// Decodes an opcode the way a CPU in `mode` executes it.
+///
+/// Extended instructions in the `0NNN` range are machine language calls outside of their mode,
+/// other extended instructions are unknown.
+pub fn decode_for(opcode: u16, mode: Mode) -> Instruction {
+    match decode(opcode) {
+        instruction if mode.supports(instruction.mode()) => instruction,
+        _ if opcode & 0xF000 == 0 => Instruction::Sys(opcode & 0x0FFF),
+        _ => Instruction::Unknown(opcode),
+    }
+}
+
+/// Decodes an opcode into the instruction it encodes in the largest instruction set.
+///
+/// Opcodes of extended instructions decode to the extended instruction even though they mean
+/// something else or nothing in the original CHIP-8, [`Instruction::mode`] tells which instruction
+/// set is needed.
+pub fn decode(opcode: u16) -> Instruction {
+    use Instruction::*;
+
+    let x = ((opcode & 0x0F00) >> 8) as u8;
+    let y = ((opcode & 0x00F0) >> 4) as u8;
+    let n = (opcode & 0x000F) as u8;
+    let nn = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    match ((opcode & 0xF000) >> 12, x, y, n) {
+        (0x0, 0x0, 0xC, n) => Scd(n),
+        (0x0, 0x0, 0xD, n) => Scu(n),
+        (0x0, 0x0, 0xE, 0x0) => Cls,
+        (0x0, 0x0, 0xE, 0xE) => Ret,
+        (0x0, 0x0, 0xF, 0xB) => Scr,
+        (0x0, 0x0, 0xF, 0xC) => Scl,
+        (0x0, 0x0, 0xF, 0xD) => Exit,
+        (0x0, 0x0, 0xF, 0xE) => Low,
+        (0x0, 0x0, 0xF, 0xF) => High,
+        (0x0, _, _, _) => Sys(nnn),
+        (0x1, _, _, _) => Jp(nnn),
+        (0x2, _, _, _) => Call(nnn),
+        (0x3, _, _, _) => SeVxByte { x, nn },
+        (0x4, _, _, _) => SneVxByte { x, nn },
+        (0x5, _, _, 0x0) => SeVxVy { x, y },
+        (0x5, _, _, 0x2) => SaveRange { x, y },
+        (0x5, _, _, 0x3) => LoadRange { x, y },
+        (0x6, _, _, _) => LdVxByte { x, nn },
+        (0x7, _, _, _) => AddVxByte { x, nn },
+        (0x8, _, _, 0x0) => LdVxVy { x, y },
+        (0x8, _, _, 0x1) => Or { x, y },
+        (0x8, _, _, 0x2) => And { x, y },
+        (0x8, _, _, 0x3) => Xor { x, y },
+        (0x8, _, _, 0x4) => AddVxVy { x, y },
+        (0x8, _, _, 0x5) => Sub { x, y },
+        (0x8, _, _, 0x6) => Shr { x, y },
+        (0x8, _, _, 0x7) => Subn { x, y },
+        (0x8, _, _, 0xE) => Shl { x, y },
+        (0x9, _, _, 0x0) => SneVxVy { x, y },
+        (0xA, _, _, _) => LdI(nnn),
+        (0xB, _, _, _) => JpV0(nnn),
+        (0xC, _, _, _) => Rnd { x, nn },
+        (0xD, _, _, _) => Drw { x, y, n },
+        (0xE, _, 0x9, 0xE) => Skp(x),
+        (0xE, _, 0xA, 0x1) => Sknp(x),
+        (0xF, 0x0, 0x0, 0x0) => LdILong,
+        (0xF, _, 0x0, 0x1) => Plane(x),
+        (0xF, 0x0, 0x0, 0x2) => Audio,
+        (0xF, _, 0x0, 0x7) => LdVxDt(x),
+        (0xF, _, 0x0, 0xA) => LdVxK(x),
+        (0xF, _, 0x1, 0x5) => LdDtVx(x),
+        (0xF, _, 0x1, 0x8) => LdStVx(x),
+        (0xF, _, 0x1, 0xE) => AddIVx(x),
+        (0xF, _, 0x2, 0x9) => LdFVx(x),
+        (0xF, _, 0x3, 0x0) => LdHfVx(x),
+        (0xF, _, 0x3, 0x3) => LdBVx(x),
+        (0xF, _, 0x3, 0xA) => Pitch(x),
+        (0xF, _, 0x5, 0x5) => LdIVx(x),
+        (0xF, _, 0x6, 0x5) => LdVxI(x),
+        (0xF, _, 0x7, 0x5) => LdRVx(x),
+        (0xF, _, 0x8, 0x5) => LdVxR(x),
+        _ => Unknown(opcode),
+    }
+}
+
+impl Instruction {
+    /// Encodes the instruction back into its opcode, the inverse of [`decode`].
+    ///
+    /// Operands are masked to the bits available in the opcode.
+    pub fn encode(self) -> u16 {
+        use Instruction::*;
+
+        fn xy(high: u16, x: u8, y: u8, n: u16) -> u16 {
+            high << 12 | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | n
+        }
+        fn xnn(high: u16, x: u8, nn: u8) -> u16 {
+            high << 12 | (x as u16 & 0xF) << 8 | nn as u16
+        }
+        fn fx(x: u8, low: u16) -> u16 {
+            0xF000 | (x as u16 & 0xF) << 8 | low
+        }
+
+        match self {
+            Scd(n) => 0x00C0 | (n as u16 & 0xF),
+            Scu(n) => 0x00D0 | (n as u16 & 0xF),
+            Cls => 0x00E0,
+            Ret => 0x00EE,
+            Scr => 0x00FB,
+            Scl => 0x00FC,
+            Exit => 0x00FD,
+            Low => 0x00FE,
+            High => 0x00FF,
+            Sys(addr) => addr & 0xFFF,
+            Jp(addr) => 0x1000 | (addr & 0xFFF),
+            Call(addr) => 0x2000 | (addr & 0xFFF),
+            SeVxByte { x, nn } => xnn(0x3, x, nn),
+            SneVxByte { x, nn } => xnn(0x4, x, nn),
+            SeVxVy { x, y } => xy(0x5, x, y, 0x0),
+            SaveRange { x, y } => xy(0x5, x, y, 0x2),
+            LoadRange { x, y } => xy(0x5, x, y, 0x3),
+            LdVxByte { x, nn } => xnn(0x6, x, nn),
+            AddVxByte { x, nn } => xnn(0x7, x, nn),
+            LdVxVy { x, y } => xy(0x8, x, y, 0x0),
+            Or { x, y } => xy(0x8, x, y, 0x1),
+            And { x, y } => xy(0x8, x, y, 0x2),
+            Xor { x, y } => xy(0x8, x, y, 0x3),
+            AddVxVy { x, y } => xy(0x8, x, y, 0x4),
+            Sub { x, y } => xy(0x8, x, y, 0x5),
+            Shr { x, y } => xy(0x8, x, y, 0x6),
+            Subn { x, y } => xy(0x8, x, y, 0x7),
+            Shl { x, y } => xy(0x8, x, y, 0xE),
+            SneVxVy { x, y } => xy(0x9, x, y, 0x0),
+            LdI(addr) => 0xA000 | (addr & 0xFFF),
+            JpV0(addr) => 0xB000 | (addr & 0xFFF),
+            Rnd { x, nn } => xnn(0xC, x, nn),
+            Drw { x, y, n } => xy(0xD, x, y, n as u16 & 0xF),
+            Skp(x) => xnn(0xE, x, 0x9E),
+            Sknp(x) => xnn(0xE, x, 0xA1),
+            LdILong => 0xF000,
+            Plane(n) => fx(n, 0x01),
+            Audio => 0xF002,
+            LdVxDt(x) => fx(x, 0x07),
+            LdVxK(x) => fx(x, 0x0A),
+            LdDtVx(x) => fx(x, 0x15),
+            LdStVx(x) => fx(x, 0x18),
+            AddIVx(x) => fx(x, 0x1E),
+            LdFVx(x) => fx(x, 0x29),
+            LdHfVx(x) => fx(x, 0x30),
+            LdBVx(x) => fx(x, 0x33),
+            Pitch(x) => fx(x, 0x3A),
+            LdIVx(x) => fx(x, 0x55),
+            LdVxI(x) => fx(x, 0x65),
+            LdRVx(x) => fx(x, 0x75),
+            LdVxR(x) => fx(x, 0x85),
+            Unknown(opcode) => opcode,
+        }
+    }
+
+    /// The smallest instruction set containing the instruction.
+    pub fn mode(self) -> Mode {
+        use Instruction::*;
+
+        match self {
+            Scd(_) | Scr | Scl | Exit | Low | High | LdHfVx(_) | LdRVx(_) | LdVxR(_) => Mode::SuperChip,
+            Scu(_) | SaveRange { .. } | LoadRange { .. } | LdILong | Plane(_) | Audio | Pitch(_) => Mode::XoChip,
+            _ => Mode::Chip8,
+        }
+    }
+
+    /// Size of the instruction in bytes, 4 for `F000 NNNN` and 2 for everything else.
+    pub fn size(self) -> u16 {
+        if self == Instruction::LdILong {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+/// Formats the instruction in Cowgod's syntax, e.g. `LD VA, 0x02` or `DRW V0, V1, 5`.
+///
+/// `F000 NNNN` is formatted as `LD I, LONG` without its address, which isn't part of the opcode,
+/// and unknown opcodes as a `DW` data directive.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+
+        match *self {
+            Scd(n) => write!(f, "SCD {}", n),
+            Scu(n) => write!(f, "SCU {}", n),
+            Cls => write!(f, "CLS"),
+            Ret => write!(f, "RET"),
+            Scr => write!(f, "SCR"),
+            Scl => write!(f, "SCL"),
+            Exit => write!(f, "EXIT"),
+            Low => write!(f, "LOW"),
+            High => write!(f, "HIGH"),
+            Sys(addr) => write!(f, "SYS {:#05X}", addr),
+            Jp(addr) => write!(f, "JP {:#05X}", addr),
+            Call(addr) => write!(f, "CALL {:#05X}", addr),
+            SeVxByte { x, nn } => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            SneVxByte { x, nn } => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            SeVxVy { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            SaveRange { x, y } => write!(f, "SAVE V{:X}, V{:X}", x, y),
+            LoadRange { x, y } => write!(f, "LOAD V{:X}, V{:X}", x, y),
+            LdVxByte { x, nn } => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            AddVxByte { x, nn } => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            LdVxVy { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            AddVxVy { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Sub { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Shr { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Subn { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Shl { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SneVxVy { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LdI(addr) => write!(f, "LD I, {:#05X}", addr),
+            JpV0(addr) => write!(f, "JP V0, {:#05X}", addr),
+            Rnd { x, nn } => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Drw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Skp(x) => write!(f, "SKP V{:X}", x),
+            Sknp(x) => write!(f, "SKNP V{:X}", x),
+            LdILong => write!(f, "LD I, LONG"),
+            Plane(n) => write!(f, "PLANE {}", n),
+            Audio => write!(f, "AUDIO"),
+            LdVxDt(x) => write!(f, "LD V{:X}, DT", x),
+            LdVxK(x) => write!(f, "LD V{:X}, K", x),
+            LdDtVx(x) => write!(f, "LD DT, V{:X}", x),
+            LdStVx(x) => write!(f, "LD ST, V{:X}", x),
+            AddIVx(x) => write!(f, "ADD I, V{:X}", x),
+            LdFVx(x) => write!(f, "LD F, V{:X}", x),
+            LdHfVx(x) => write!(f, "LD HF, V{:X}", x),
+            LdBVx(x) => write!(f, "LD B, V{:X}", x),
+            Pitch(x) => write!(f, "PITCH V{:X}", x),
+            LdIVx(x) => write!(f, "LD [I], V{:X}", x),
+            LdVxI(x) => write!(f, "LD V{:X}, [I]", x),
+            LdRVx(x) => write!(f, "LD R, V{:X}", x),
+            LdVxR(x) => write!(f, "LD V{:X}, R", x),
+            Unknown(opcode) => write!(f, "DW {:#06X}", opcode),
+        }
+    }
+}
@@ -1,47 +1,53 @@
-use minifb::{Window, KeyRepeat, Key};
-use std::rc::Rc;
-use std::cell::RefCell;
+/// Supplies the state of the hexadecimal keypad to the [`CPU`](crate::CPU).
+///
+/// Keypad Layout:
+///
+/// ```text
+/// 1    2    3    C
+/// 4    5    6    D
+/// 7    8    9    E
+/// A    0    B    F
+/// ```
+pub trait Keyboard {
+    fn is_key_pressed(&self, key_num: u8) -> bool;
 
-pub struct Keyboard {
-    pub(crate) window: Rc<RefCell<Window>>,
+    /// Called by the CPU before the first instruction of every frame, so keyboards that change
+    /// once per frame, like movie recording and replay, line up with the emulated time.
+    fn start_frame(&mut self) {}
 }
 
-impl Keyboard {
-    pub fn new(window: Rc<RefCell<Window>>) -> Keyboard {
-        Keyboard {
-            window,
+/// A [`Keyboard`] whose keys are pressed and released programmatically.
+#[derive(Default)]
+pub struct MemoryKeyboard {
+    keys: [bool; 16],
+}
+
+impl MemoryKeyboard {
+    pub fn new() -> MemoryKeyboard {
+        MemoryKeyboard::default()
+    }
+
+    /// Presses a key. Keys above F don't exist, so they're ignored and never reported as pressed.
+    pub fn press(&mut self, key_num: u8) {
+        if let Some(key) = self.keys.get_mut(key_num as usize) {
+            *key = true;
         }
     }
 
-    /*
-    Keypad Layout:
+    pub fn release(&mut self, key_num: u8) {
+        if let Some(key) = self.keys.get_mut(key_num as usize) {
+            *key = false;
+        }
+    }
 
-    1	2	3	C
-    4	5	6	D
-    7	8	9	E
-    A	0	B	F
+    /// Replaces the state of every key at once, indexed by key number.
+    pub fn set_keys(&mut self, keys: [bool; 16]) {
+        self.keys = keys;
+    }
+}
 
-     */
-    pub fn is_key_pressed(&self, key_num: u8) -> bool {
-        let key = match key_num {
-            1 => Key::Key1,
-            2 => Key::Key2,
-            3 => Key::Key3,
-            0xC => Key::Key4,
-            4 => Key::Q,
-            5 => Key::W,
-            6 => Key::E,
-            0xD => Key::R,
-            7 => Key::A,
-            8 => Key::S,
-            9 => Key::D,
-            0xE => Key::F,
-            0xA => Key::Z,
-            0x0 => Key::X,
-            0xB => Key::C,
-            0xF => Key::V,
-            _ => Key::Space,
-        };
-        self.window.borrow().is_key_pressed(key, KeyRepeat::Yes)
+impl Keyboard for MemoryKeyboard {
+    fn is_key_pressed(&self, key_num: u8) -> bool {
+        self.keys.get(key_num as usize).copied().unwrap_or(false)
     }
 }
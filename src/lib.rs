@@ -0,0 +1,43 @@
+//! A CHIP-8 interpreter.
+//!
+//! The [`CPU`] is generic over the [`Display`] it draws to, the [`Keyboard`] it reads input
+//! from and the [`Audio`] sink playing its buzzer, so the interpreter can be embedded in any
+//! frontend. The windowed frontend shipped with the `chipper` binary lives behind the `window`
+//! feature, while [`MemoryDisplay`], [`MemoryKeyboard`] and [`NullAudio`] allow the CPU to run
+//! headless, e.g. in tests or on servers.
+
+pub mod asm;
+pub mod audio;
+pub mod cpu;
+pub mod debugger;
+pub mod disasm;
+pub mod display;
+pub mod dump;
+pub mod error;
+pub mod framebuffer;
+pub mod instruction;
+pub mod keyboard;
+pub mod movie;
+pub mod palette;
+pub mod quirks;
+pub mod rewind;
+pub mod rng;
+pub mod rom;
+pub mod scale;
+pub mod scheduler;
+pub mod state;
+pub mod trace;
+
+pub use audio::{Audio, NullAudio, RecordingAudio};
+pub use cpu::{Mode, CPU};
+pub use display::{Display, MemoryDisplay};
+pub use error::ChipError;
+pub use framebuffer::Framebuffer;
+pub use instruction::{decode, Instruction};
+pub use keyboard::{Keyboard, MemoryKeyboard};
+pub use palette::Palette;
+pub use quirks::Quirks;
+pub use rng::Rng;
+pub use rom::ROM;
+pub use scheduler::Scheduler;
+pub use state::SaveState;
@@ -1,41 +1,374 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::process;
 
-use minifb::{Window, WindowOptions};
+use chipper::debugger::Debugger;
+use chipper::movie::{Movie, ReplayKeyboard};
+use chipper::state::SaveState;
+use chipper::trace::{BinaryTrace, TextTrace, Trace};
+use chipper::{dump, Audio, Display, Keyboard, CPU, Framebuffer, MemoryDisplay, MemoryKeyboard, Palette, Rng, ROM};
 
-use cpu::CPU;
-use display::Display;
-use keyboard::Keyboard;
-use rom::ROM;
+use cli::{CliError, Options};
 
-mod cpu;
-mod rom;
-mod keyboard;
-mod display;
+mod cli;
+#[cfg(feature = "window")]
+mod speaker;
+#[cfg(feature = "window")]
+mod window;
 
-const WIDTH: usize = 64 * SCALE_FACTOR;
-const HEIGHT: usize = 32 * SCALE_FACTOR;
-const SCALE_FACTOR: usize = 10;
+fn main() {
+    let mut options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(CliError::Help) => {
+            println!("{}", CliError::Help);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
 
+    let rom = ROM::new(&options.rom_path).unwrap_or_else(|e| {
+        eprintln!("error: could not load ROM '{}': {}", options.rom_path, e);
+        process::exit(1);
+    });
 
-fn main() {
-    let mut window = Window::new("Test", WIDTH, HEIGHT, WindowOptions::default())
+    if let Some(path) = &options.theme {
+        let theme = fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|text| Palette::from_theme(&text).map_err(|e| e.to_string()));
+        match theme {
+            Ok(palette) => options.palette = palette,
+            Err(e) => {
+                eprintln!("error: could not load theme '{}': {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Movies need a seed to replay the same random numbers
+    if options.record.is_some() && options.seed.is_none() {
+        options.seed = Some(Rng::from_entropy().next_u64());
+    }
+
+    if options.debugger {
+        run_debugger(&options, rom);
+    } else if options.headless {
+        run_headless(&options, rom);
+    } else {
+        run_windowed(&options, rom);
+    }
+}
+
+fn run_headless(options: &Options, rom: ROM) {
+    match &options.replay {
+        Some(path) => {
+            let movie = read_movie(path, &rom);
+            let mut cpu = CPU::new(ReplayKeyboard::new(&movie), MemoryDisplay::new());
+            configure(&mut cpu, options, &rom, Some(&movie));
+            run_headless_cpu(&mut cpu, options, |cpu| cpu.keyboard().is_finished());
+        }
+        None => {
+            let mut cpu = CPU::new(MemoryKeyboard::new(), MemoryDisplay::new());
+            configure(&mut cpu, options, &rom, None);
+            run_headless_cpu(&mut cpu, options, |_| false);
+        }
+    }
+}
+
+/// Runs until the conditions given in the options or `finished` stop it, then writes the dump.
+fn run_headless_cpu<K, F>(cpu: &mut CPU<K, MemoryDisplay>, options: &Options, finished: F)
+    where K: Keyboard, F: Fn(&CPU<K, MemoryDisplay>) -> bool {
+    let until_pc = options.until_pc;
+    let until_halt = options.until_halt;
+    let result = cpu.run_until(options.cycles, |cpu| {
+        Some(cpu.pc()) == until_pc || (until_halt && cpu.is_halted()) || finished(cpu)
+    });
+    finish_trace(cpu);
+
+    if let Some(path) = &options.dump {
+        if let Err(e) = write_dump(path, cpu.framebuffer(), options.palette) {
+            eprintln!("error: could not write '{}': {}", path, e);
+            process::exit(1);
+        }
+    }
+
+    // The screen is still dumped when the program fails, it often shows what went wrong
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+
+    if let Some(address) = until_pc {
+        if cpu.pc() != address {
+            eprintln!("error: PC did not reach {:#05X} within {} cycles", address, options.cycles);
+            process::exit(1);
+        }
+    }
+    if until_halt && !cpu.is_halted() {
+        eprintln!("error: program did not halt within {} cycles", options.cycles);
+        process::exit(1);
+    }
+}
+
+fn run_debugger(options: &Options, rom: ROM) {
+    let mut cpu = CPU::new(MemoryKeyboard::new(), MemoryDisplay::new());
+    configure(&mut cpu, options, &rom, None);
+
+    println!("Debugging {}, type help for a list of commands", options.rom_path);
+    let stdin = io::stdin();
+    let result = Debugger::new().repl(&mut cpu, stdin.lock(), io::stdout());
+    finish_trace(&mut cpu);
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Applies the options to a new CPU and loads the ROM into it. A movie being replayed overrides
+/// the options that affect how the program runs.
+fn configure<K: Keyboard, D: Display, A: Audio>(cpu: &mut CPU<K, D, A>, options: &Options, rom: &ROM, replay: Option<&Movie>) {
+    match replay {
+        Some(movie) => movie.configure(cpu),
+        None => {
+            cpu.set_mode(options.mode);
+            cpu.set_instructions_per_frame(options.instructions_per_frame);
+            cpu.set_quirks(options.quirks);
+            if let Some(seed) = options.seed {
+                cpu.set_rng(Rng::new(seed));
+            }
+        }
+    }
+    if let Err(e) = cpu.load_rom(rom.clone()) {
+        eprintln!("error: could not load ROM: {}", e);
+        process::exit(1);
+    }
+
+    if options.debug {
+        cpu.enable_debug();
+    }
+
+    if options.load_state {
+        match read_state(&options.state_path) {
+            Ok(state) => cpu.load_state(&state),
+            Err(e) => {
+                eprintln!("error: could not load state '{}': {}", options.state_path, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = &options.trace {
+        match open_trace(path) {
+            Ok(trace) => cpu.set_trace(Some(trace)),
+            Err(e) => {
+                eprintln!("error: could not create '{}': {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn open_trace(path: &str) -> io::Result<Box<dyn Trace>> {
+    if path == "-" {
+        return Ok(Box::new(TextTrace::new(io::stdout())));
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    if path.ends_with(".bin") {
+        Ok(Box::new(BinaryTrace::new(file)))
+    } else {
+        Ok(Box::new(TextTrace::new(file)))
+    }
+}
+
+/// Writes out the rest of the trace, which would be lost when exiting the process.
+fn finish_trace<K: Keyboard, D: Display, A: Audio>(cpu: &mut CPU<K, D, A>) {
+    if let Err(e) = cpu.flush_trace() {
+        eprintln!("error: could not write trace: {}", e);
+        process::exit(1);
+    }
+}
+
+fn read_state(path: &str) -> chipper::error::Result<SaveState> {
+    SaveState::from_bytes(&fs::read(path)?)
+}
+
+/// Reads the movie at `path`, exiting unless it was recorded with `rom`.
+fn read_movie(path: &str, rom: &ROM) -> Movie {
+    let movie = fs::read(path).map_err(Into::into).and_then(|data| Movie::from_bytes(&data));
+    match movie {
+        Ok(movie) if movie.matches(rom) => movie,
+        Ok(_) => {
+            eprintln!("error: movie '{}' was recorded with a different ROM", path);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: could not load movie '{}': {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn write_dump(path: &str, buf: &Framebuffer, palette: Palette) -> io::Result<()> {
+    if path == "-" {
+        return io::stdout().write_all(dump::to_ascii(buf).as_bytes());
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    if path.ends_with(".pbm") {
+        dump::write_pbm(&mut file, buf)?;
+    } else if path.ends_with(".png") {
+        dump::write_png(&mut file, buf, palette)?;
+    } else {
+        file.write_all(dump::to_ascii(buf).as_bytes())?;
+    }
+    file.flush()
+}
+
+#[cfg(feature = "window")]
+fn run_windowed(options: &Options, rom: ROM) {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use chipper::display::{HEIGHT, WIDTH};
+    use chipper::movie::MovieRecorder;
+
+    use speaker::Speaker;
+    use window::{WindowDisplay, WindowKeyboard};
+
+    let (width, height) = if options.fullscreen { options.fullscreen_size } else { (WIDTH * options.scale, HEIGHT * options.scale) };
+    let window = window::open_window(&window_title(options), width, height, options.fullscreen)
         .unwrap_or_else(|e| {
-            panic!("{}", e);
+            eprintln!("error: could not open window: {}", e);
+            process::exit(1);
         });
-    // window.limit_update_rate(Some(std::time::Duration::from_micros(100)));
-    window.set_key_repeat_delay(0.01);
-    window.set_key_repeat_rate(0.01);
 
     let window_ref = Rc::new(RefCell::new(window));
-    let display = Display::new(window_ref.clone());
-    let keyboard = Keyboard::new(window_ref.clone());
-    let mut cpu = CPU::new(keyboard, display);
+    let display = WindowDisplay::new(window_ref.clone(), options.scaling, options.palette);
+    let keyboard = WindowKeyboard::new(window_ref.clone());
 
-    let rom = ROM::new("programs/PONG");
+    let result = if let Some(path) = &options.replay {
+        let movie = read_movie(path, &rom);
+        let mut cpu = CPU::with_audio(ReplayKeyboard::new(&movie), display, Speaker::open());
+        configure(&mut cpu, options, &rom, Some(&movie));
+        run_window_loop(&mut cpu, &window_ref, options, false)
+    } else if let Some(path) = &options.record {
+        let mut cpu = CPU::with_audio(MovieRecorder::new(keyboard), display, Speaker::open());
+        configure(&mut cpu, options, &rom, None);
+        let mut movie = Movie::new(&rom, options.seed.unwrap(), &cpu);
+        let result = run_window_loop(&mut cpu, &window_ref, options, false);
 
-    cpu.load_rom(rom);
+        // The movie is saved even when the program fails, replaying it reproduces the failure
+        movie.frames = cpu.keyboard().frames().to_vec();
+        match fs::write(path, movie.to_bytes()) {
+            Ok(()) => eprintln!("recorded {} frames to '{}'", movie.frames.len(), path),
+            Err(e) => eprintln!("error: could not write movie '{}': {}", path, e),
+        }
+        result
+    } else {
+        let mut cpu = CPU::with_audio(keyboard, display, Speaker::open());
+        configure(&mut cpu, options, &rom, None);
+        run_window_loop(&mut cpu, &window_ref, options, true)
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(feature = "window")]
+fn window_title(options: &Options) -> String {
+    format!("chipper - {}", options.rom_path)
+}
+
+/// Runs the program at 60 frames per second until the window is closed. Save states and rewinding
+/// are only available with `time_travel`, as they would break movies.
+#[cfg(feature = "window")]
+fn run_window_loop<K: Keyboard>(
+    cpu: &mut CPU<K, window::WindowDisplay, speaker::Speaker>,
+    window_ref: &std::rc::Rc<std::cell::RefCell<minifb::Window>>,
+    options: &Options,
+    time_travel: bool,
+) -> chipper::error::Result<()> {
+    use minifb::{Key, KeyRepeat};
+
+    use chipper::display::{HEIGHT, WIDTH};
+    use chipper::rewind::Rewind;
+    use chipper::Scheduler;
+
+    let mut rewind = Rewind::with_seconds(if time_travel { options.rewind_seconds } else { 0 });
+    rewind.record(cpu);
+
+    let mut fullscreen = options.fullscreen;
+    let mut windowed_size = (WIDTH * options.scale, HEIGHT * options.scale);
+
+    let mut scheduler = Scheduler::new();
+    while window_ref.borrow().is_open() && !cpu.has_exited() {
+        for _ in 0..scheduler.wait_for_frame() {
+            // Holding Backspace plays the recorded frames backwards instead of running the program
+            if time_travel && window_ref.borrow().is_key_down(Key::Backspace) {
+                rewind.step_back(cpu);
+                continue;
+            }
+            if let Err(e) = cpu.run_frame() {
+                finish_trace(cpu);
+                return Err(e);
+            }
+            rewind.record(cpu);
+        }
+        // Also polls the keyboard, which otherwise only happens when the screen changes
+        cpu.display_mut().present();
+
+        let (save, load, toggle_fullscreen) = {
+            let window = window_ref.borrow();
+            (window.is_key_pressed(Key::F5, KeyRepeat::No), window.is_key_pressed(Key::F9, KeyRepeat::No),
+             window.is_key_pressed(Key::F11, KeyRepeat::No))
+        };
+        if toggle_fullscreen {
+            // The window is replaced by one with the other style, the display and keyboard pick
+            // it up as they share it
+            let (width, height) = if fullscreen { windowed_size } else { options.fullscreen_size };
+            match window::open_window(&window_title(options), width, height, !fullscreen) {
+                Ok(window) => {
+                    if !fullscreen {
+                        windowed_size = window_ref.borrow().get_size();
+                    }
+                    *window_ref.borrow_mut() = window;
+                    fullscreen = !fullscreen;
+                }
+                Err(e) => eprintln!("error: could not reopen window: {}", e),
+            }
+        }
+        // Failing to save or load shouldn't end the game, so errors are only reported
+        if time_travel && save {
+            match fs::write(&options.state_path, cpu.save_state().to_bytes()) {
+                Ok(()) => eprintln!("saved state to '{}'", options.state_path),
+                Err(e) => eprintln!("error: could not save state to '{}': {}", options.state_path, e),
+            }
+        }
+        if time_travel && load {
+            match read_state(&options.state_path) {
+                Ok(state) => {
+                    cpu.load_state(&state);
+                    // Rewinding stops at the loaded state rather than jumping back across the load
+                    rewind.clear();
+                    rewind.record(cpu);
+                    eprintln!("loaded state from '{}'", options.state_path);
+                }
+                Err(e) => eprintln!("error: could not load state '{}': {}", options.state_path, e),
+            }
+        }
+    }
+    finish_trace(cpu);
+    Ok(())
+}
 
-    // cpu.enable_debug();
-    cpu.run();
+#[cfg(not(feature = "window"))]
+fn run_windowed(_options: &Options, _rom: ROM) {
+    eprintln!("error: chipper was built without the `window` feature, only --headless is available");
+    process::exit(2);
 }
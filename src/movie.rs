@@ -0,0 +1,256 @@
+//! Movies, recordings of the keypad state of every frame that replay a play session exactly.
+//!
+//! Replaying gives the same result as the recording because everything else a program depends on
+//! is fixed by the movie's header: the ROM, the seed of the random number generator, the mode,
+//! the quirks and the instructions per frame. Keys only change between frames, see
+//! [`Keyboard::start_frame`].
+
+use crate::audio::Audio;
+use crate::cpu::{Mode, CPU};
+use crate::display::Display;
+use crate::error::{ChipError, Result};
+use crate::keyboard::Keyboard;
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+use crate::rom::ROM;
+
+/// Magic bytes starting a movie file, followed by the format version.
+const MAGIC: &[u8; 4] = b"C8MV";
+
+/// Version of the movie format written by [`Movie::to_bytes`].
+pub const VERSION: u8 = 1;
+
+/// The header of a movie and the keys pressed in every frame, as a mask with bit N set when key
+/// N is down.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    /// [`rom_hash`] of the ROM the movie was recorded with.
+    pub rom_hash: u64,
+    pub seed: u64,
+    pub mode: Mode,
+    pub quirks: Quirks,
+    pub instructions_per_frame: usize,
+    pub frames: Vec<u16>,
+}
+
+/// The 64 bit FNV-1a hash of a ROM's contents, identifying the ROM a movie belongs to.
+pub fn rom_hash(rom: &ROM) -> u64 {
+    rom.data[..rom.size].iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+impl Movie {
+    /// Creates a movie without frames for `cpu`, which runs `rom` with an [`Rng`](crate::Rng)
+    /// seeded with `seed`.
+    pub fn new<K: Keyboard, D: Display, A: Audio>(rom: &ROM, seed: u64, cpu: &CPU<K, D, A>) -> Movie {
+        Movie {
+            rom_hash: rom_hash(rom),
+            seed,
+            mode: cpu.mode(),
+            quirks: cpu.quirks(),
+            instructions_per_frame: cpu.instructions_per_frame(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Serializes the movie: a `C8MV` header and the format version, the ROM hash, seed, mode,
+    /// instructions per frame, the quirks as text and the frames, all big endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let quirks = self.quirks.to_string();
+        let mut out = Vec::with_capacity(32 + quirks.len() + self.frames.len() * 2);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.rom_hash.to_be_bytes());
+        out.extend_from_slice(&self.seed.to_be_bytes());
+        out.push(match self.mode {
+            Mode::Chip8 => 0,
+            Mode::SuperChip => 1,
+            Mode::XoChip => 2,
+        });
+        out.extend_from_slice(&(self.instructions_per_frame as u32).to_be_bytes());
+        out.push(quirks.len() as u8);
+        out.extend_from_slice(quirks.as_bytes());
+        out.extend_from_slice(&(self.frames.len() as u32).to_be_bytes());
+        for keys in &self.frames {
+            out.extend_from_slice(&keys.to_be_bytes());
+        }
+        out
+    }
+
+    /// Reads a movie written by [`to_bytes`](Movie::to_bytes).
+    pub fn from_bytes(data: &[u8]) -> Result<Movie> {
+        let mut data = data;
+        let mut take = |len: usize| -> Result<&[u8]> {
+            if data.len() < len {
+                return Err(invalid("movie ends early"));
+            }
+            let (bytes, rest) = data.split_at(len);
+            data = rest;
+            Ok(bytes)
+        };
+
+        if take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a chipper movie"));
+        }
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported movie version {}", version)));
+        }
+        let rom_hash = u64::from_be_bytes(array(take(8)?));
+        let seed = u64::from_be_bytes(array(take(8)?));
+        let mode = match take(1)?[0] {
+            0 => Mode::Chip8,
+            1 => Mode::SuperChip,
+            2 => Mode::XoChip,
+            mode => return Err(invalid(&format!("unknown mode {}", mode))),
+        };
+        let instructions_per_frame = u32::from_be_bytes(array(take(4)?)) as usize;
+        if instructions_per_frame == 0 {
+            return Err(invalid("no instructions per frame"));
+        }
+        let quirks_len = take(1)?[0] as usize;
+        let quirks = std::str::from_utf8(take(quirks_len)?).ok()
+            .and_then(|quirks| quirks.parse().ok())
+            .ok_or_else(|| invalid("unknown quirks"))?;
+        let frame_count = u32::from_be_bytes(array(take(4)?)) as usize;
+        let frames = take(frame_count * 2)?.chunks(2)
+            .map(|keys| u16::from_be_bytes([keys[0], keys[1]]))
+            .collect();
+        if !data.is_empty() {
+            return Err(invalid("unexpected data after the last frame"));
+        }
+
+        Ok(Movie {
+            rom_hash,
+            seed,
+            mode,
+            quirks,
+            instructions_per_frame,
+            frames,
+        })
+    }
+
+    /// Sets the mode, quirks, instructions per frame and random number generator of `cpu` to the
+    /// ones the movie was recorded with. As the mode decides the memory size, the ROM should be
+    /// loaded afterwards.
+    pub fn configure<K: Keyboard, D: Display, A: Audio>(&self, cpu: &mut CPU<K, D, A>) {
+        cpu.set_mode(self.mode);
+        cpu.set_quirks(self.quirks);
+        cpu.set_instructions_per_frame(self.instructions_per_frame);
+        cpu.set_rng(Rng::new(self.seed));
+    }
+
+    /// Whether the movie was recorded with `rom`.
+    pub fn matches(&self, rom: &ROM) -> bool {
+        self.rom_hash == rom_hash(rom)
+    }
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+    array
+}
+
+fn invalid(reason: &str) -> ChipError {
+    ChipError::InvalidMovie(String::from(reason))
+}
+
+fn key_mask<K: Keyboard>(keyboard: &K) -> u16 {
+    (0..16).filter(|key| keyboard.is_key_pressed(*key)).fold(0, |mask, key| mask | 1 << key)
+}
+
+/// A [`Keyboard`] recording the keys of another keyboard every frame.
+///
+/// The keys are read once at the start of each frame and stay the same until the next one, so
+/// the program sees exactly what ends up in the movie.
+pub struct MovieRecorder<K> {
+    keyboard: K,
+    keys: u16,
+    frames: Vec<u16>,
+}
+
+impl<K: Keyboard> MovieRecorder<K> {
+    pub fn new(keyboard: K) -> MovieRecorder<K> {
+        MovieRecorder {
+            keyboard,
+            keys: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn keyboard(&self) -> &K {
+        &self.keyboard
+    }
+
+    pub fn keyboard_mut(&mut self) -> &mut K {
+        &mut self.keyboard
+    }
+
+    /// The keys of every frame started so far.
+    pub fn frames(&self) -> &[u16] {
+        &self.frames
+    }
+}
+
+impl<K: Keyboard> Keyboard for MovieRecorder<K> {
+    fn is_key_pressed(&self, key_num: u8) -> bool {
+        key_num < 16 && self.keys & 1 << key_num != 0
+    }
+
+    fn start_frame(&mut self) {
+        self.keyboard.start_frame();
+        self.keys = key_mask(&self.keyboard);
+        self.frames.push(self.keys);
+    }
+}
+
+/// A [`Keyboard`] pressing the keys recorded in a movie, frame by frame. Once the movie is over
+/// no keys are pressed.
+pub struct ReplayKeyboard {
+    frames: Vec<u16>,
+    next: usize,
+    keys: u16,
+    finished: bool,
+}
+
+impl ReplayKeyboard {
+    pub fn new(movie: &Movie) -> ReplayKeyboard {
+        ReplayKeyboard {
+            frames: movie.frames.clone(),
+            next: 0,
+            keys: 0,
+            finished: false,
+        }
+    }
+
+    /// Number of frames replayed so far.
+    pub fn frame(&self) -> usize {
+        self.next
+    }
+
+    /// Whether a frame was started after the last one in the movie.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Keyboard for ReplayKeyboard {
+    fn is_key_pressed(&self, key_num: u8) -> bool {
+        key_num < 16 && self.keys & 1 << key_num != 0
+    }
+
+    fn start_frame(&mut self) {
+        match self.frames.get(self.next) {
+            Some(keys) => {
+                self.keys = *keys;
+                self.next += 1;
+            }
+            None => {
+                self.keys = 0;
+                self.finished = true;
+            }
+        }
+    }
+}
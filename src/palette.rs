@@ -0,0 +1,153 @@
+//! The colours the screen is shown in, picked for each pixel when it's presented rather than
+//! stored in the [`Framebuffer`](crate::Framebuffer).
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The RGB colour of each pixel value, i.e. of each combination of drawing planes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// Unset pixels.
+    pub background: u32,
+    /// Pixels set in the first plane, the only one outside of XO-CHIP.
+    pub foreground: u32,
+    /// Pixels set only in the second XO-CHIP plane.
+    pub second_plane: u32,
+    /// Pixels set in both XO-CHIP planes.
+    pub both_planes: u32,
+}
+
+/// Names of the palettes accepted by [`Palette::preset`].
+pub const PRESETS: [&str; 5] = ["chipper", "classic", "amber", "green", "lcd"];
+
+/// Keys of a theme file, see [`Palette::from_theme`].
+pub const THEME_KEYS: [&str; 5] = ["preset", "background", "foreground", "second-plane", "both-planes"];
+
+impl Palette {
+    pub const fn new(background: u32, foreground: u32, second_plane: u32, both_planes: u32) -> Palette {
+        Palette {
+            background,
+            foreground,
+            second_plane,
+            both_planes,
+        }
+    }
+
+    /// Returns a built-in palette:
+    ///
+    /// * `chipper`: yellow on black, the default
+    /// * `classic`: white on black
+    /// * `amber` and `green`: the phosphor colours of old monochrome monitors
+    /// * `lcd`: dark green on a pale green, like early handheld screens
+    pub fn preset(name: &str) -> Option<Palette> {
+        let palette = match name {
+            "chipper" => Palette::new(0x000000, 0xC8C864, 0xFF6600, 0x662200),
+            "classic" => Palette::new(0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555),
+            "amber" => Palette::new(0x1A0F00, 0xFFB000, 0xB36B00, 0x5C3A00),
+            "green" => Palette::new(0x001100, 0x33FF33, 0x1F9F1F, 0x0F4F0F),
+            "lcd" => Palette::new(0x9BBC0F, 0x0F380F, 0x306230, 0x8BAC0F),
+            _ => return None,
+        };
+        Some(palette)
+    }
+
+    /// The colour of a pixel set in the planes of `pixel`.
+    pub fn color(&self, pixel: u8) -> u32 {
+        match pixel & 0b11 {
+            0 => self.background,
+            1 => self.foreground,
+            2 => self.second_plane,
+            _ => self.both_planes,
+        }
+    }
+
+    /// Reads a theme, made of `key = value` lines where the key is `preset` with the name of a
+    /// preset to start from, or one of `background`, `foreground`, `second-plane` and
+    /// `both-planes` with an RGB hex colour. Lines are applied in order, starting from the
+    /// default palette. Blank lines and lines starting with `#` are skipped.
+    pub fn from_theme(text: &str) -> Result<Palette, ParsePaletteError> {
+        let mut palette = Palette::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason: String| ParsePaletteError(format!("line {}: {}", number + 1, reason));
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(error(String::from("expected a 'key = value' line"))),
+            };
+
+            if key == "preset" {
+                palette = Palette::preset(value).ok_or_else(|| error(unknown_preset(value)))?;
+                continue;
+            }
+            let color = parse_color(value).ok_or_else(|| error(format!("invalid RGB hex colour '{}'", value)))?;
+            match key {
+                "background" => palette.background = color,
+                "foreground" => palette.foreground = color,
+                "second-plane" => palette.second_plane = color,
+                "both-planes" => palette.both_planes = color,
+                _ => return Err(error(format!("unknown key '{}', keys are {}", key, THEME_KEYS.join(", ")))),
+            }
+        }
+        Ok(palette)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::preset("chipper").unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePaletteError(String);
+
+impl fmt::Display for ParsePaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePaletteError {}
+
+/// Parses the name of a preset, or a comma separated list of the background and foreground
+/// colours as RGB hex, e.g. `000000,c8c864`, optionally followed by the colours of the second
+/// plane and of both planes. Colours that aren't given are the default ones.
+impl FromStr for Palette {
+    type Err = ParsePaletteError;
+
+    fn from_str(s: &str) -> Result<Palette, ParsePaletteError> {
+        if let Some(palette) = Palette::preset(s.trim()) {
+            return Ok(palette);
+        }
+
+        let colors = s.split(',')
+            .map(parse_color)
+            .collect::<Option<Vec<u32>>>();
+        let default = Palette::default();
+        match colors.as_deref() {
+            Some(&[background, foreground]) => Ok(Palette { background, foreground, ..default }),
+            Some(&[background, foreground, second_plane, both_planes]) => {
+                Ok(Palette::new(background, foreground, second_plane, both_planes))
+            }
+            _ => Err(ParsePaletteError(format!(
+                "invalid palette '{}', expected a preset ({}) or two or four RGB hex colours like 000000,c8c864",
+                s, PRESETS.join(", ")))),
+        }
+    }
+}
+
+fn unknown_preset(name: &str) -> String {
+    format!("unknown palette preset '{}', presets are {}", name, PRESETS.join(", "))
+}
+
+/// Parses a colour like `c8c864` or `#C8C864`.
+fn parse_color(value: &str) -> Option<u32> {
+    let value = value.trim().trim_start_matches('#');
+    if value.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(value, 16).ok()
+}
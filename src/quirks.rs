@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Behaviour of the opcodes that CHIP-8 interpreters historically disagree on.
+///
+/// Different ROMs were written for different interpreters, so the right set of quirks depends on
+/// the ROM. [`Quirks::preset`] provides the behaviour of the common interpreters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift VX in place instead of storing the shifted VY in VX.
+    pub shift_vx: bool,
+    /// `FX55`/`FX65` leave I pointing past the last register accessed instead of leaving it unchanged.
+    pub increment_i: bool,
+    /// `BNNN` jumps to `NNN + VX` (as `BXNN`) instead of `NNN + V0`.
+    pub jump_vx: bool,
+    /// `DXYN` wraps sprites around the edges of the screen instead of clipping them. The position
+    /// a sprite starts at wraps either way.
+    pub wrap_sprites: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset VF to 0, as a side effect of how the COSMAC VIP ran them.
+    pub vf_reset: bool,
+    /// `FX1E` sets VF to 1 when I goes past the end of memory and to 0 otherwise, which only the
+    /// Amiga interpreter did.
+    pub i_overflow: bool,
+}
+
+/// Names of the flags, as accepted when parsing quirks.
+pub const FLAGS: [&str; 6] = ["shift-vx", "increment-i", "jump-vx", "wrap-sprites", "vf-reset", "i-overflow"];
+
+/// Names of the presets accepted by [`Quirks::preset`].
+pub const PRESETS: [&str; 5] = ["chipper", "vip", "chip48", "schip", "xochip"];
+
+impl Quirks {
+    /// Returns the quirks of a well known interpreter:
+    ///
+    /// - `chipper`: the behaviour `chipper` always had, which is the default
+    /// - `vip`: the original COSMAC VIP interpreter
+    /// - `chip48`: CHIP-48 on the HP-48 calculators
+    /// - `schip`: SUPER-CHIP 1.1
+    /// - `xochip`: XO-CHIP as implemented by Octo
+    pub fn preset(name: &str) -> Option<Quirks> {
+        let quirks = match name {
+            "chipper" => Quirks {
+                shift_vx: true,
+                increment_i: true,
+                jump_vx: false,
+                wrap_sprites: false,
+                vf_reset: false,
+                i_overflow: false,
+            },
+            "vip" => Quirks {
+                shift_vx: false,
+                increment_i: true,
+                jump_vx: false,
+                wrap_sprites: false,
+                vf_reset: true,
+                i_overflow: false,
+            },
+            "chip48" | "schip" => Quirks {
+                shift_vx: true,
+                increment_i: false,
+                jump_vx: true,
+                wrap_sprites: false,
+                vf_reset: false,
+                i_overflow: false,
+            },
+            "xochip" => Quirks {
+                shift_vx: false,
+                increment_i: true,
+                jump_vx: false,
+                wrap_sprites: true,
+                vf_reset: false,
+                i_overflow: false,
+            },
+            _ => return None,
+        };
+        Some(quirks)
+    }
+
+    fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "shift-vx" => Some(&mut self.shift_vx),
+            "increment-i" => Some(&mut self.increment_i),
+            "jump-vx" => Some(&mut self.jump_vx),
+            "wrap-sprites" => Some(&mut self.wrap_sprites),
+            "vf-reset" => Some(&mut self.vf_reset),
+            "i-overflow" => Some(&mut self.i_overflow),
+            _ => None,
+        }
+    }
+}
+
+/// Lists every flag, prefixed with `no-` when disabled, which parses back into the same quirks.
+impl fmt::Display for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut quirks = *self;
+        for (i, name) in FLAGS.iter().enumerate() {
+            let enabled = *quirks.flag_mut(name).unwrap();
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{}{}{}", separator, if enabled { "" } else { "no-" }, name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::preset("chipper").unwrap()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseQuirksError(String);
+
+impl fmt::Display for ParseQuirksError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseQuirksError {}
+
+/// Parses a comma separated list of an optional preset followed by flag overrides, where a flag
+/// is enabled by its name and disabled by its name prefixed with `no-`, e.g. `vip,wrap-sprites`
+/// or `schip,no-jump-vx`. Overrides without a preset apply to the default quirks.
+impl FromStr for Quirks {
+    type Err = ParseQuirksError;
+
+    fn from_str(s: &str) -> Result<Quirks, ParseQuirksError> {
+        let mut parts = s.split(',').map(str::trim).peekable();
+
+        let mut quirks = match parts.peek().and_then(|name| Quirks::preset(name)) {
+            Some(preset) => {
+                parts.next();
+                preset
+            }
+            None => Quirks::default(),
+        };
+
+        for part in parts {
+            let (name, enabled) = match part.strip_prefix("no-") {
+                Some(name) => (name, false),
+                None => (part, true),
+            };
+            match quirks.flag_mut(name) {
+                Some(flag) => *flag = enabled,
+                None => return Err(ParseQuirksError(format!(
+                    "unknown quirks preset or flag '{}', presets are {} and flags are {}",
+                    part, PRESETS.join(", "), FLAGS.join(", ")))),
+            }
+        }
+
+        Ok(quirks)
+    }
+}
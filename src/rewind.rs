@@ -0,0 +1,169 @@
+//! Rewinding of gameplay, by keeping a snapshot of every frame for the last few seconds.
+
+use std::collections::VecDeque;
+
+use crate::audio::Audio;
+use crate::cpu::CPU;
+use crate::display::Display;
+use crate::keyboard::Keyboard;
+use crate::scheduler::FRAME_RATE;
+use crate::state::SaveState;
+
+/// Seconds of gameplay kept unless configured otherwise.
+pub const DEFAULT_SECONDS: usize = 10;
+
+/// A ring buffer of the states the CPU was in at the end of each recent frame.
+///
+/// Only the latest state is kept in full. Every older state is stored as the difference to the
+/// state recorded after it, which is tiny as most of the memory stays the same from one frame to
+/// the next. Stepping back applies the newest difference, and once the buffer is full recording
+/// a frame drops the oldest one.
+pub struct Rewind {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    /// Creates a buffer able to step back up to `frames` frames.
+    pub fn new(frames: usize) -> Rewind {
+        Rewind {
+            capacity: frames,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Creates a buffer holding `seconds` of 60 Hz frames.
+    pub fn with_seconds(seconds: usize) -> Rewind {
+        Rewind::new(seconds * FRAME_RATE as usize)
+    }
+
+    /// Most frames that can be stepped back.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of frames that can currently be stepped back.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Forgets every recorded frame, e.g. after loading a save state.
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Bytes used by the recorded frames.
+    pub fn memory_usage(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Records the current state of the CPU, meant to be called at the end of every frame.
+    pub fn record<K: Keyboard, D: Display, A: Audio>(&mut self, cpu: &CPU<K, D, A>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let state = cpu.save_state().to_bytes();
+        if let Some(latest) = self.latest.take() {
+            if self.deltas.len() == self.capacity {
+                self.deltas.pop_front();
+            }
+            self.deltas.push_back(delta(&state, &latest));
+        }
+        self.latest = Some(state);
+    }
+
+    /// Puts the CPU back into the state recorded the frame before the latest one, returning false
+    /// when there's nothing left to rewind.
+    pub fn step_back<K: Keyboard, D: Display, A: Audio>(&mut self, cpu: &mut CPU<K, D, A>) -> bool {
+        let (latest, delta) = match (self.latest.take(), self.deltas.pop_back()) {
+            (Some(latest), Some(delta)) => (latest, delta),
+            (latest, _) => {
+                self.latest = latest;
+                return false;
+            }
+        };
+        let previous = apply(&latest, &delta);
+        // Only states taken by the CPU end up in the buffer, so they always read back
+        let state = SaveState::from_bytes(&previous).expect("rewind buffer holds an invalid state");
+        cpu.load_state(&state);
+        self.latest = Some(previous);
+        true
+    }
+}
+
+/// Encodes how to turn `from` into `to`: the length of `to`, followed by runs of unchanged bytes
+/// to skip and runs of changed bytes XORed with the ones in `from`, with lengths as varints.
+fn delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, to.len());
+
+    let at = |bytes: &[u8], index: usize| bytes.get(index).copied().unwrap_or(0);
+    let mut index = 0;
+    let mut unchanged = 0;
+    while index < to.len() {
+        if at(from, index) == to[index] {
+            unchanged += 1;
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < to.len() && at(from, index) != to[index] {
+            index += 1;
+        }
+        write_varint(&mut out, unchanged);
+        write_varint(&mut out, index - start);
+        out.extend((start..index).map(|i| at(from, i) ^ to[i]));
+        unchanged = 0;
+    }
+    out
+}
+
+/// Turns `from` back into the bytes a [`delta`] was computed for.
+fn apply(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut delta = delta;
+    let len = read_varint(&mut delta);
+    let mut out = from.to_vec();
+    out.resize(len, 0);
+
+    let mut index = 0;
+    while !delta.is_empty() {
+        index += read_varint(&mut delta);
+        let changed = read_varint(&mut delta);
+        for (byte, xor) in out[index..index + changed].iter_mut().zip(&delta[..changed]) {
+            *byte ^= xor;
+        }
+        delta = &delta[changed..];
+        index += changed;
+    }
+    out
+}
+
+/// Writes seven bits per byte, least significant first, with the top bit set on all but the last.
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = input[0];
+        *input = &input[1..];
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
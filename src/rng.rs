@@ -0,0 +1,59 @@
+//! The random number generator behind `CXNN`, which can be seeded so runs are reproducible.
+
+/// A xorshift64* generator.
+///
+/// Its whole state is a single `u64`, which makes it cheap to store in save states. It's not
+/// suitable for anything but games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator that always produces the same numbers for the same seed.
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on a zero state and needs a while to get going from states with few
+        // bits set, so the seed is scrambled with SplitMix64 first
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng::from_state(z)
+    }
+
+    /// Creates a generator with a random seed, the default for CPUs.
+    pub fn from_entropy() -> Rng {
+        Rng::new(rand::random())
+    }
+
+    /// Restores a generator from its [`state`](Rng::state).
+    pub fn from_state(state: u64) -> Rng {
+        Rng {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    /// The internal state, which continues the same sequence when passed to
+    /// [`from_state`](Rng::from_state).
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A random byte, taken from the high bits which are the most random ones.
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Rng {
+        Rng::from_entropy()
+    }
+}
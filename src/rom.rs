@@ -1,24 +1,40 @@
 use std::io::prelude::*;
 use std::fs::File;
 
-#[derive(Clone, Copy)]
+use crate::error::{ChipError, Result};
+
+/// Largest program that fits in memory, 65536 bytes of XO-CHIP memory - 512 unaddressable slots.
+///
+/// Classic CHIP-8 programs can only use 3584 bytes, loading a larger ROM into a CPU that isn't
+/// in XO-CHIP mode fails.
+pub const MAX_SIZE: usize = 65536 - 0x200;
+
+#[derive(Clone)]
 pub struct ROM {
-    // 4096 (mem size) - unaddressable 512 slots
-    pub data: [u8; 3584],
+    pub data: Vec<u8>,
     pub size: usize, // True size
 }
 
 impl ROM {
-    pub fn new(filename: &str) -> ROM {
-        let mut f = File::open(filename).unwrap();
+    pub fn new(filename: &str) -> Result<ROM> {
+        let f = File::open(filename)?;
 
-        let mut data = [0; 3584];
+        // Read one byte more than fits, so oversized ROMs are detected without reading them whole
+        let mut data = Vec::new();
+        f.take(MAX_SIZE as u64 + 1).read_to_end(&mut data)?;
 
-        let size = f.read(&mut data).unwrap();
+        ROM::from_bytes(&data)
+    }
 
-        ROM {
-            data,
-            size,
+    /// Creates a ROM from a program in memory.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ROM> {
+        if bytes.len() > MAX_SIZE {
+            return Err(ChipError::RomTooLarge { size: bytes.len(), max: MAX_SIZE });
         }
+
+        Ok(ROM {
+            data: bytes.to_vec(),
+            size: bytes.len(),
+        })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,202 @@
+//! Scaling of the framebuffer to a window of any size, for frontends drawing RGB pixels.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::framebuffer::Framebuffer;
+use crate::palette::Palette;
+
+/// How the screen is scaled to fit the window. Either way the aspect ratio is kept and the space
+/// left around the screen is filled with the background colour.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Scales by the largest whole number that fits, so every CHIP-8 pixel has the same size.
+    #[default]
+    Integer,
+    /// Scales as large as fits, so some pixels may end up a window pixel wider than others.
+    Fit,
+}
+
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Scaling, String> {
+        match s {
+            "integer" => Ok(Scaling::Integer),
+            "fit" => Ok(Scaling::Fit),
+            _ => Err(format!("unknown scaling '{}', expected integer or fit", s)),
+        }
+    }
+}
+
+impl fmt::Display for Scaling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scaling::Integer => write!(f, "integer"),
+            Scaling::Fit => write!(f, "fit"),
+        }
+    }
+}
+
+/// The area of the window the screen is drawn to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Centres a `screen` sized framebuffer in a `window` sized buffer, both given as width and
+    /// height. A window smaller than the screen gets a viewport scaled down to fit even with
+    /// integer scaling, as showing nothing at all isn't useful.
+    pub fn new(screen: (usize, usize), window: (usize, usize), scaling: Scaling) -> Viewport {
+        let (screen_width, screen_height) = screen;
+        let (window_width, window_height) = window;
+        if screen_width == 0 || screen_height == 0 {
+            return Viewport::default();
+        }
+
+        let integer = (window_width / screen_width).min(window_height / screen_height);
+        let (width, height) = if scaling == Scaling::Integer && integer > 0 {
+            (screen_width * integer, screen_height * integer)
+        } else if window_width * screen_height <= window_height * screen_width {
+            // Limited by the width, letterboxed at the top and bottom
+            (window_width, window_width * screen_height / screen_width)
+        } else {
+            (window_height * screen_width / screen_height, window_height)
+        };
+
+        Viewport {
+            x: (window_width - width) / 2,
+            y: (window_height - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+/// Renders framebuffers into an RGB buffer of any size.
+///
+/// Which framebuffer pixel each window pixel shows is worked out once whenever the window or
+/// screen size changes, and only the rows of the screen that changed since the last render are
+/// drawn again.
+pub struct Scaler {
+    scaling: Scaling,
+    palette: Palette,
+    width: usize,
+    height: usize,
+    buffer: Vec<u32>,
+    viewport: Viewport,
+    /// Framebuffer column shown in each column of the viewport.
+    columns: Vec<usize>,
+    /// Framebuffer row shown in each row of the viewport.
+    rows: Vec<usize>,
+    /// Resolution of the framebuffer the tables were computed for.
+    screen: (usize, usize),
+    /// Pixels of the last framebuffer rendered with the current tables.
+    last: Option<Vec<u8>>,
+}
+
+impl Scaler {
+    /// Creates a scaler for a `width` by `height` buffer.
+    pub fn new(width: usize, height: usize, scaling: Scaling, palette: Palette) -> Scaler {
+        Scaler {
+            scaling,
+            palette,
+            width,
+            height,
+            buffer: vec![palette.background; width * height],
+            viewport: Viewport::default(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+            screen: (0, 0),
+            last: None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The rendered pixels, row by row.
+    pub fn buffer(&self) -> &[u32] {
+        &self.buffer
+    }
+
+    /// Where the last framebuffer was drawn in the buffer.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Changes the size of the buffer, so the next render draws everything again.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.buffer = vec![self.palette.background; width * height];
+            self.screen = (0, 0);
+            self.last = None;
+        }
+    }
+
+    /// Draws `buf` into the buffer, returning whether any pixel of the buffer changed.
+    pub fn render(&mut self, buf: &Framebuffer) -> bool {
+        if (buf.width(), buf.height()) != self.screen {
+            self.layout(buf.width(), buf.height());
+        }
+
+        let pixels: Vec<u8> = buf.pixels().collect();
+        if self.last.as_ref() == Some(&pixels) {
+            return false;
+        }
+
+        let screen_width = buf.width();
+        let Viewport { x, y, width, .. } = self.viewport;
+        let mut previous = None;
+        for (row, source) in self.rows.iter().enumerate() {
+            let line = &pixels[source * screen_width..(source + 1) * screen_width];
+            let unchanged = self.last.as_ref()
+                .is_some_and(|last| &last[source * screen_width..(source + 1) * screen_width] == line);
+            if unchanged {
+                continue;
+            }
+
+            let start = (y + row) * self.width + x;
+            match previous {
+                // Neighbouring rows showing the same framebuffer row are copies of each other
+                Some((previous_source, previous_start)) if previous_source == *source => {
+                    self.buffer.copy_within(previous_start..previous_start + width, start);
+                }
+                _ => {
+                    let palette = self.palette;
+                    let out = &mut self.buffer[start..start + width];
+                    for (pixel, column) in out.iter_mut().zip(&self.columns) {
+                        *pixel = palette.color(line[*column]);
+                    }
+                }
+            }
+            previous = Some((*source, start));
+        }
+
+        self.last = Some(pixels);
+        true
+    }
+
+    /// Works out the viewport for a `screen_width` by `screen_height` framebuffer and which of
+    /// its pixels each pixel of the viewport shows, clearing the letterbox around it.
+    fn layout(&mut self, screen_width: usize, screen_height: usize) {
+        self.screen = (screen_width, screen_height);
+        self.viewport = Viewport::new(self.screen, (self.width, self.height), self.scaling);
+        self.columns = (0..self.viewport.width).map(|x| x * screen_width / self.viewport.width).collect();
+        self.rows = (0..self.viewport.height).map(|y| y * screen_height / self.viewport.height).collect();
+        let background = self.palette.background;
+        self.buffer.iter_mut().for_each(|pixel| *pixel = background);
+        self.last = None;
+    }
+}
@@ -0,0 +1,54 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Rate at which the CHIP-8 timers count down and the screen is refreshed.
+pub const FRAME_RATE: u32 = 60;
+
+/// How many frames may be emulated at once to catch up after the host stalled, anything beyond
+/// is dropped so the emulator doesn't fast forward afterwards.
+const MAX_CATCH_UP_FRAMES: u32 = 5;
+
+/// Paces emulation against wall-clock time in frames of 1/60th of a second.
+///
+/// The CPU itself counts time in frames of [`CPU::instructions_per_frame`](crate::CPU::instructions_per_frame)
+/// instructions, so all a frontend has to do is run as many frames as the scheduler asks for.
+pub struct Scheduler {
+    frame_duration: Duration,
+    next_frame: Instant,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            frame_duration: Duration::from_secs(1) / FRAME_RATE,
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Sleeps until the next frame is due and returns the number of frames that should be
+    /// emulated, which is more than one if the host fell behind.
+    pub fn wait_for_frame(&mut self) -> u32 {
+        let now = Instant::now();
+        if now < self.next_frame {
+            sleep(self.next_frame - now);
+        }
+
+        let now = Instant::now();
+        let mut frames = 0;
+        while self.next_frame <= now && frames < MAX_CATCH_UP_FRAMES {
+            self.next_frame += self.frame_duration;
+            frames += 1;
+        }
+        if self.next_frame <= now {
+            self.next_frame = now + self.frame_duration;
+        }
+
+        frames
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Scheduler {
+        Scheduler::new()
+    }
+}
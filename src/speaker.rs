@@ -0,0 +1,118 @@
+//! Sound for the windowed frontend. minifb has no audio output, so the samples of a [`Buzzer`]
+//! are streamed to the system's command line audio player instead.
+//!
+//! That's `aplay` from alsa-utils or `pacat` from PulseAudio, which usually means Linux. Without
+//! either of them the frontend warns on stderr and falls back to the terminal bell.
+
+use std::io::{self, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chipper::audio::{Audio, Buzzer};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Samples generated at once, 10 ms worth.
+const BLOCK: usize = SAMPLE_RATE as usize / 100;
+/// How far ahead of the clock samples are generated, which is about the delay before a change of
+/// the buzzer is heard.
+const LEAD: Duration = Duration::from_millis(50);
+const VOLUME: f32 = 0.2;
+
+/// Players tried in order, each reading 16 bit mono samples at [`SAMPLE_RATE`] from stdin.
+const PLAYERS: [(&str, &[&str]); 2] = [
+    ("aplay", &["-q", "-t", "raw", "-f", "S16_LE", "-r", "44100", "-c", "1", "--buffer-time=50000", "-"]),
+    ("pacat", &["--raw", "--format=s16le", "--rate=44100", "--channels=1", "--latency-msec=50"]),
+];
+
+/// An [`Audio`] sink playing the buzzer for as long as the sound timer runs, and XO-CHIP audio
+/// patterns. When no player is available, or the player fails, it falls back to ringing the
+/// terminal bell whenever the buzzer starts.
+pub struct Speaker {
+    buzzer: Arc<Mutex<Buzzer>>,
+    player: Option<Child>,
+    /// Set once the player can't take samples anymore, and to stop the thread feeding it.
+    stopped: Arc<AtomicBool>,
+}
+
+impl Speaker {
+    pub fn open() -> Speaker {
+        let buzzer = Arc::new(Mutex::new(Buzzer::new(SAMPLE_RATE)));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let player = PLAYERS.iter().find_map(|(program, args)| {
+            Command::new(program).args(*args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()
+        });
+        match player {
+            Some(mut player) => {
+                let stdin = player.stdin.take().expect("player stdin is piped");
+                let (shared_buzzer, shared_stopped) = (buzzer.clone(), stopped.clone());
+                thread::spawn(move || feed(stdin, &shared_buzzer, &shared_stopped));
+                Speaker { buzzer, player: Some(player), stopped }
+            }
+            None => {
+                eprintln!("warning: no audio player found, install aplay or pacat for sound; ringing the terminal bell instead");
+                stopped.store(true, Ordering::Relaxed);
+                Speaker { buzzer, player: None, stopped }
+            }
+        }
+    }
+}
+
+/// Writes samples to the player as the clock advances, until it fails or the speaker is dropped.
+fn feed(mut stdin: ChildStdin, buzzer: &Mutex<Buzzer>, stopped: &AtomicBool) {
+    let start = Instant::now();
+    let mut written = 0u64;
+    let mut samples = vec![0.0; BLOCK];
+    let mut bytes = Vec::with_capacity(BLOCK * 2);
+    while !stopped.load(Ordering::Relaxed) {
+        let due = ((start.elapsed() + LEAD).as_secs_f64() * SAMPLE_RATE as f64) as u64;
+        if written + BLOCK as u64 > due {
+            thread::sleep(Duration::from_millis(5));
+            continue;
+        }
+
+        buzzer.lock().unwrap().fill(&mut samples, VOLUME);
+        bytes.clear();
+        for sample in &samples {
+            bytes.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        if stdin.write_all(&bytes).is_err() {
+            eprintln!("warning: the audio player stopped; ringing the terminal bell instead");
+            stopped.store(true, Ordering::Relaxed);
+            return;
+        }
+        written += BLOCK as u64;
+    }
+}
+
+impl Audio for Speaker {
+    fn set_tone(&mut self, playing: bool) {
+        self.buzzer.lock().unwrap().set_tone(playing);
+        if playing && self.stopped.load(Ordering::Relaxed) {
+            let mut stderr = io::stderr();
+            let _ = stderr.write_all(b"\x07").and_then(|_| stderr.flush());
+        }
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8; 16], pitch: u8) {
+        self.buzzer.lock().unwrap().set_pattern(pattern, pitch);
+    }
+}
+
+impl Drop for Speaker {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(player) = &mut self.player {
+            let _ = player.kill();
+            let _ = player.wait();
+        }
+    }
+}
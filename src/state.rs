@@ -0,0 +1,231 @@
+//! Snapshots of the whole machine, which can be written to a file and restored later to jump
+//! straight back to a point in a program.
+
+use crate::cpu::Mode;
+use crate::display::{HEIGHT, HIRES_HEIGHT, HIRES_WIDTH, WIDTH};
+use crate::error::{ChipError, Result};
+use crate::framebuffer::{Framebuffer, ALL_PLANES};
+use crate::rng::Rng;
+
+/// Magic bytes starting a save state file, followed by the format version.
+const MAGIC: &[u8; 4] = b"C8ST";
+
+/// Version of the save state format written by [`SaveState::to_bytes`].
+pub const VERSION: u8 = 1;
+
+/// Value stored in place of the awaited key when `FX0A` isn't waiting for a key to be released.
+const NO_KEY: u8 = 0xFF;
+
+/// The state of a [`CPU`](crate::CPU), taken with [`CPU::save_state`](crate::CPU::save_state).
+///
+/// Only the machine itself is saved, the quirks, instruction rate and the keyboard, display and
+/// audio devices are left to the CPU the state is loaded into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveState {
+    pub(crate) mode: Mode,
+    pub(crate) v: [u8; 16],
+    pub(crate) i: u16,
+    pub(crate) pc: u16,
+    pub(crate) memory: Vec<u8>,
+    pub(crate) delay_timer: u8,
+    pub(crate) sound_timer: u8,
+    pub(crate) stack: [u16; 16],
+    pub(crate) sp: usize,
+    pub(crate) awaited_key: Option<u8>,
+    pub(crate) rpl_flags: [u8; 16],
+    pub(crate) planes: u8,
+    pub(crate) audio_pattern: [u8; 16],
+    pub(crate) pitch: u8,
+    pub(crate) exited: bool,
+    pub(crate) frame_cycles: usize,
+    pub(crate) output_buffer: Framebuffer,
+    pub(crate) rng: Rng,
+}
+
+impl SaveState {
+    /// The program counter at the time the state was taken.
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Serializes the state, starting with a `C8ST` header and the format version. Multi-byte
+    /// values are big endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.memory.len() + self.output_buffer.width() * self.output_buffer.height() + 128);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(match self.mode {
+            Mode::Chip8 => 0,
+            Mode::SuperChip => 1,
+            Mode::XoChip => 2,
+        });
+
+        out.extend_from_slice(&self.v);
+        out.extend_from_slice(&self.i.to_be_bytes());
+        out.extend_from_slice(&self.pc.to_be_bytes());
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        for address in &self.stack {
+            out.extend_from_slice(&address.to_be_bytes());
+        }
+        out.push(self.sp as u8);
+        out.push(self.awaited_key.unwrap_or(NO_KEY));
+        out.extend_from_slice(&self.rpl_flags);
+        out.push(self.planes);
+        out.extend_from_slice(&self.audio_pattern);
+        out.push(self.pitch);
+        out.push(self.exited as u8);
+        out.extend_from_slice(&(self.frame_cycles as u32).to_be_bytes());
+        out.extend_from_slice(&self.rng.state().to_be_bytes());
+
+        out.extend_from_slice(&(self.memory.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.memory);
+
+        out.extend_from_slice(&(self.output_buffer.width() as u16).to_be_bytes());
+        out.extend_from_slice(&(self.output_buffer.height() as u16).to_be_bytes());
+        out.extend(self.output_buffer.pixels());
+        out
+    }
+
+    /// Reads a state written by [`to_bytes`](SaveState::to_bytes), checking that it describes a
+    /// machine the CPU could have been in.
+    pub fn from_bytes(data: &[u8]) -> Result<SaveState> {
+        let mut reader = Reader { data };
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a chipper save state"));
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported save state version {}", version)));
+        }
+        let mode = match reader.u8()? {
+            0 => Mode::Chip8,
+            1 => Mode::SuperChip,
+            2 => Mode::XoChip,
+            mode => return Err(invalid(&format!("unknown mode {}", mode))),
+        };
+
+        let v = reader.array()?;
+        let i = reader.u16()?;
+        let pc = reader.u16()?;
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let mut stack = [0; 16];
+        for address in stack.iter_mut() {
+            *address = reader.u16()?;
+        }
+        let sp = reader.u8()? as usize;
+        if sp > stack.len() {
+            return Err(invalid("stack pointer past the end of the stack"));
+        }
+        let awaited_key = match reader.u8()? {
+            NO_KEY => None,
+            key if key < 16 => Some(key),
+            _ => return Err(invalid("awaited key isn't a key")),
+        };
+        let rpl_flags = reader.array()?;
+        let planes = reader.u8()?;
+        if planes > ALL_PLANES {
+            return Err(invalid("plane mask selects planes that don't exist"));
+        }
+        let audio_pattern = reader.array()?;
+        let pitch = reader.u8()?;
+        let exited = reader.u8()? != 0;
+        let frame_cycles = reader.u32()? as usize;
+        let rng = Rng::from_state(reader.u64()?);
+
+        let memory_size = reader.u32()? as usize;
+        if memory_size != mode.memory_size() {
+            return Err(invalid("memory size doesn't match the mode"));
+        }
+        let memory = reader.bytes(memory_size)?.to_vec();
+
+        let width = reader.u16()? as usize;
+        let height = reader.u16()? as usize;
+        if (width, height) != (WIDTH, HEIGHT) && (width, height) != (HIRES_WIDTH, HIRES_HEIGHT) {
+            return Err(invalid("unsupported screen resolution"));
+        }
+        let mut output_buffer = Framebuffer::new(width, height);
+        for (index, value) in reader.bytes(width * height)?.iter().enumerate() {
+            if *value > ALL_PLANES {
+                return Err(invalid("pixel set in a plane that doesn't exist"));
+            }
+            output_buffer.set_pixel(index % width, index / width, *value);
+        }
+
+        if !reader.data.is_empty() {
+            return Err(invalid("unexpected data after the end of the save state"));
+        }
+
+        Ok(SaveState {
+            mode,
+            v,
+            i,
+            pc,
+            memory,
+            delay_timer,
+            sound_timer,
+            stack,
+            sp,
+            awaited_key,
+            rpl_flags,
+            planes,
+            audio_pattern,
+            pitch,
+            exited,
+            frame_cycles,
+            output_buffer,
+            rng,
+        })
+    }
+}
+
+fn invalid(reason: &str) -> ChipError {
+    ChipError::InvalidSaveState(String::from(reason))
+}
+
+/// Reads big endian values from the front of a byte slice.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid("save state ends early"));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn array(&mut self) -> Result<[u8; 16]> {
+        let mut array = [0; 16];
+        array.copy_from_slice(self.bytes(16)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+}
@@ -0,0 +1,173 @@
+//! Recording of the instructions a program executes, for diffing runs between builds or against
+//! other emulators.
+
+use std::io;
+use std::io::prelude::*;
+
+use crate::cpu::Mode;
+use crate::instruction::{decode_for, Instruction};
+
+/// Magic bytes starting a binary trace, followed by the format version.
+const MAGIC: &[u8; 4] = b"C8TR";
+
+/// Version of the binary trace format written by [`BinaryTrace`].
+pub const VERSION: u8 = 1;
+
+/// Size of an entry in a binary trace.
+const ENTRY_SIZE: usize = 27;
+
+/// The machine state right before an instruction executes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub opcode: u16,
+    pub v: [u8; 16],
+    pub i: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// The mode the CPU decoded the opcode in.
+    pub mode: Mode,
+    /// The word following the opcode, which is the address loaded by `F000 NNNN`. Zero for every
+    /// other instruction.
+    pub operand: u16,
+}
+
+impl TraceEntry {
+    /// The instruction the opcode decodes to, in the instruction set of the CPU's mode.
+    pub fn instruction(&self) -> Instruction {
+        decode_for(self.opcode, self.mode)
+    }
+}
+
+/// Receives an entry for every instruction the CPU executes.
+///
+/// Tracing is off unless a sink is set with [`CPU::set_trace`](crate::CPU::set_trace), which
+/// leaves a single check per instruction when it isn't used.
+pub trait Trace {
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()>;
+
+    /// Writes out anything buffered.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes a line of text per instruction, with the program counter, opcode, V0 to VF, I, the
+/// delay and sound timers in hex followed by the instruction as the CPU's mode decodes it, e.g.
+///
+/// ```text
+/// 0200 00E0 00000000000000000000000000000000 0000 00 00 CLS
+/// ```
+pub struct TextTrace<W> {
+    out: W,
+}
+
+impl<W: Write> TextTrace<W> {
+    pub fn new(out: W) -> TextTrace<W> {
+        TextTrace { out }
+    }
+}
+
+impl<W: Write> Trace for TextTrace<W> {
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        write!(self.out, "{:04X} {:04X} ", entry.pc, entry.opcode)?;
+        for value in &entry.v {
+            write!(self.out, "{:02X}", value)?;
+        }
+        write!(self.out, " {:04X} {:02X} {:02X} ", entry.i, entry.delay_timer, entry.sound_timer)?;
+        match entry.instruction() {
+            Instruction::LdILong => writeln!(self.out, "LD I, LONG {:#06X}", entry.operand),
+            instruction => writeln!(self.out, "{}", instruction),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes fixed size big endian records of 27 bytes per instruction, after a header of `C8TR`
+/// and a version byte. The records hold the program counter, opcode, V0 to VF, I, the delay and
+/// sound timers, the mode and the operand, read them back with [`read_binary`].
+pub struct BinaryTrace<W> {
+    out: W,
+    started: bool,
+}
+
+impl<W: Write> BinaryTrace<W> {
+    pub fn new(out: W) -> BinaryTrace<W> {
+        BinaryTrace { out, started: false }
+    }
+}
+
+impl<W: Write> Trace for BinaryTrace<W> {
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        if !self.started {
+            self.out.write_all(MAGIC)?;
+            self.out.write_all(&[VERSION])?;
+            self.started = true;
+        }
+
+        let mut record = [0; ENTRY_SIZE];
+        record[0..2].copy_from_slice(&entry.pc.to_be_bytes());
+        record[2..4].copy_from_slice(&entry.opcode.to_be_bytes());
+        record[4..20].copy_from_slice(&entry.v);
+        record[20..22].copy_from_slice(&entry.i.to_be_bytes());
+        record[22] = entry.delay_timer;
+        record[23] = entry.sound_timer;
+        record[24] = match entry.mode {
+            Mode::Chip8 => 0,
+            Mode::SuperChip => 1,
+            Mode::XoChip => 2,
+        };
+        record[25..27].copy_from_slice(&entry.operand.to_be_bytes());
+        self.out.write_all(&record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Reads a trace written by [`BinaryTrace`].
+pub fn read_binary<R: Read>(mut input: R) -> io::Result<Vec<TraceEntry>> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    if data.len() < 5 || &data[..4] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a chipper trace"));
+    }
+    let version = data[4];
+    if version != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported trace version {}", version)));
+    }
+
+    let records = data[5..].chunks_exact(ENTRY_SIZE);
+    if !records.remainder().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "trace ends in the middle of an entry"));
+    }
+    records
+        .map(|record| {
+            let mut v = [0; 16];
+            v.copy_from_slice(&record[4..20]);
+            let mode = match record[24] {
+                0 => Mode::Chip8,
+                1 => Mode::SuperChip,
+                2 => Mode::XoChip,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown mode in trace")),
+            };
+            Ok(TraceEntry {
+                pc: u16::from_be_bytes([record[0], record[1]]),
+                opcode: u16::from_be_bytes([record[2], record[3]]),
+                v,
+                i: u16::from_be_bytes([record[20], record[21]]),
+                delay_timer: record[22],
+                sound_timer: record[23],
+                mode,
+                operand: u16::from_be_bytes([record[25], record[26]]),
+            })
+        })
+        .collect()
+}
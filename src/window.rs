@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use minifb::{Key, Window, WindowOptions};
+
+use chipper::display;
+use chipper::scale::{Scaler, Scaling};
+use chipper::{Framebuffer, Palette};
+use chipper::keyboard;
+
+/// Opens a resizable window of the given size, or a borderless window covering the top left of
+/// the screen when `fullscreen`, as minifb can't make a window fullscreen itself.
+pub fn open_window(title: &str, width: usize, height: usize, fullscreen: bool) -> minifb::Result<Window> {
+    let options = WindowOptions {
+        resize: !fullscreen,
+        borderless: fullscreen,
+        title: !fullscreen,
+        topmost: fullscreen,
+        ..WindowOptions::default()
+    };
+    let mut window = Window::new(title, width, height, options)?;
+    if fullscreen {
+        window.set_position(0, 0);
+    }
+    // Frames are paced by the scheduler
+    window.limit_update_rate(None);
+    Ok(window)
+}
+
+pub struct WindowDisplay {
+    window: Rc<RefCell<Window>>,
+    scaler: Scaler,
+    screen: Framebuffer,
+    dirty: bool,
+}
+
+impl WindowDisplay {
+    pub fn new(window: Rc<RefCell<Window>>, scaling: Scaling, palette: Palette) -> WindowDisplay {
+        let (width, height) = (*window).borrow().get_size();
+        WindowDisplay {
+            window,
+            scaler: Scaler::new(width, height, scaling, palette),
+            screen: Framebuffer::new(display::WIDTH, display::HEIGHT),
+            dirty: true,
+        }
+    }
+
+    /// Shows the latest framebuffer in the window and polls its input, meant to be called once
+    /// per frame. The screen is only scaled again when it or the size of the window changed.
+    pub fn present(&mut self) {
+        let mut window = (*self.window).borrow_mut();
+        let (width, height) = window.get_size();
+        if (width, height) != (self.scaler.width(), self.scaler.height()) {
+            self.scaler.resize(width, height);
+            self.dirty = true;
+        }
+
+        if self.dirty && self.scaler.render(&self.screen) && width > 0 && height > 0 {
+            window.update_with_buffer(self.scaler.buffer(), width, height)
+                .unwrap();
+        } else {
+            window.update();
+        }
+        self.dirty = false;
+    }
+}
+
+impl display::Display for WindowDisplay {
+    fn update_buffer(&mut self, buf: &Framebuffer) {
+        // Programs draw many times per frame, so scaling waits until the frame is presented
+        self.screen.clone_from(buf);
+        self.dirty = true;
+    }
+}
+
+pub struct WindowKeyboard {
+    window: Rc<RefCell<Window>>,
+}
+
+impl WindowKeyboard {
+    pub fn new(window: Rc<RefCell<Window>>) -> WindowKeyboard {
+        WindowKeyboard {
+            window,
+        }
+    }
+}
+
+impl keyboard::Keyboard for WindowKeyboard {
+    fn is_key_pressed(&self, key_num: u8) -> bool {
+        let key = match key_num {
+            1 => Key::Key1,
+            2 => Key::Key2,
+            3 => Key::Key3,
+            0xC => Key::Key4,
+            4 => Key::Q,
+            5 => Key::W,
+            6 => Key::E,
+            0xD => Key::R,
+            7 => Key::A,
+            8 => Key::S,
+            9 => Key::D,
+            0xE => Key::F,
+            0xA => Key::Z,
+            0x0 => Key::X,
+            0xB => Key::C,
+            0xF => Key::V,
+            // Keys above F don't exist
+            _ => return false,
+        };
+        self.window.borrow().is_key_down(key)
+    }
+}
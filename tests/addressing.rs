@@ -0,0 +1,46 @@
+mod common;
+
+use chipper::Mode;
+use common::{cpu_in_mode, cpu_with_program, run};
+
+#[test]
+fn jump_with_offset_wraps_to_12_bits() {
+    // V0 = 0x12, jump to 0xFF0 + V0
+    let cpu = run(cpu_with_program(&[0x60, 0x12, 0xBF, 0xF0]), 2);
+    assert_eq!(cpu.pc(), 0x002);
+}
+
+#[test]
+fn add_to_i_wraps_to_12_bits() {
+    // I = 0xFFF, V3 = 0xFF, I += V3
+    let cpu = run(cpu_with_program(&[0xAF, 0xFF, 0x63, 0xFF, 0xF3, 0x1E]), 3);
+    assert_eq!(cpu.i(), 0x0FE);
+}
+
+#[test]
+fn load_store_increment_wraps_i() {
+    // I = 0xFFE, store V0 and V1, which ends exactly at the end of memory
+    let cpu = run(cpu_with_program(&[0xAF, 0xFE, 0xF1, 0x55]), 2);
+    assert_eq!(cpu.i(), 0x000);
+}
+
+#[test]
+fn program_counter_wraps_at_the_end_of_memory() {
+    // Jump to 0xFFC, where V1 = 5 and V2 = 6 run into 0x000
+    let mut rom = vec![0; 0xFFC - 0x200 + 4];
+    rom[..2].copy_from_slice(&[0x1F, 0xFC]);
+    rom[0xFFC - 0x200..].copy_from_slice(&[0x61, 0x05, 0x62, 0x06]);
+    let cpu = run(cpu_with_program(&rom), 3);
+    assert_eq!(cpu.registers()[1..3], [5, 6]);
+    assert_eq!(cpu.pc(), 0x000);
+}
+
+#[test]
+fn xochip_addresses_are_16_bits() {
+    // I = 0x0FFF, V0 = 1, I += V0, I = 0xFFFF, I += V0
+    let program = [0xAF, 0xFF, 0x60, 0x01, 0xF0, 0x1E, 0xF0, 0x00, 0xFF, 0xFF, 0xF0, 0x1E];
+    let cpu = run(cpu_in_mode(&program, Mode::XoChip), 3);
+    assert_eq!(cpu.i(), 0x1000);
+    let cpu = run(cpu, 2);
+    assert_eq!(cpu.i(), 0x0000);
+}
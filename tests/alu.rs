@@ -0,0 +1,131 @@
+mod common;
+
+use chipper::{Mode, Quirks};
+use common::{cpu_with, run};
+
+/// Loads the registers with the given values, then executes `opcode` and returns the registers.
+fn execute(registers: &[(u8, u8)], opcode: u16, quirks: Quirks) -> [u8; 16] {
+    let mut program = Vec::new();
+    for (register, value) in registers {
+        program.extend_from_slice(&[0x60 | register, *value]);
+    }
+    program.extend_from_slice(&opcode.to_be_bytes());
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, quirks), registers.len() + 1);
+    *cpu.registers()
+}
+
+fn chipper() -> Quirks {
+    Quirks::default()
+}
+
+fn vip() -> Quirks {
+    Quirks::preset("vip").unwrap()
+}
+
+#[test]
+fn load() {
+    let v = execute(&[(1, 0x05), (2, 0x09)], 0x8120, chipper());
+    assert_eq!((v[1], v[2]), (0x09, 0x09));
+
+    assert_eq!(execute(&[(2, 0x09)], 0x8F20, chipper())[0xF], 0x09);
+    assert_eq!(execute(&[(1, 0x05), (0xF, 0x07)], 0x81F0, chipper())[1], 0x07);
+}
+
+#[test]
+fn logical_operations_keep_vf_without_vf_reset() {
+    let cases = [(0x8121, 0x0E), (0x8122, 0x08), (0x8123, 0x06)];
+    for (opcode, expected) in cases.iter() {
+        let v = execute(&[(1, 0x0C), (2, 0x0A), (0xF, 0x07)], *opcode, chipper());
+        assert_eq!((v[1], v[0xF]), (*expected, 0x07), "{:04X}", opcode);
+    }
+
+    // With VF as the destination or an operand it's an ordinary register
+    assert_eq!(execute(&[(2, 0x03), (0xF, 0x30)], 0x8F21, chipper())[0xF], 0x33);
+    assert_eq!(execute(&[(2, 0x3C), (0xF, 0x30)], 0x8F22, chipper())[0xF], 0x30);
+    assert_eq!(execute(&[(2, 0x3C), (0xF, 0x30)], 0x8F23, chipper())[0xF], 0x0C);
+    assert_eq!(execute(&[(1, 0x0C), (0xF, 0x03)], 0x81F1, chipper())[1], 0x0F);
+}
+
+#[test]
+fn logical_operations_clear_vf_with_vf_reset() {
+    assert!(vip().vf_reset);
+    let cases = [(0x8121, 0x0E), (0x8122, 0x08), (0x8123, 0x06)];
+    for (opcode, expected) in cases.iter() {
+        let v = execute(&[(1, 0x0C), (2, 0x0A), (0xF, 0x07)], *opcode, vip());
+        assert_eq!((v[1], v[0xF]), (*expected, 0), "{:04X}", opcode);
+    }
+
+    // The result is computed from VF before it's cleared
+    let v = execute(&[(1, 0x0C), (0xF, 0x03)], 0x81F1, vip());
+    assert_eq!((v[1], v[0xF]), (0x0F, 0));
+    assert_eq!(execute(&[(2, 0x03), (0xF, 0x30)], 0x8F21, vip())[0xF], 0);
+}
+
+#[test]
+fn add_carries_only_past_255() {
+    let cases = [
+        (0xFE, 0x01, 0xFF, 0),
+        (0xFF, 0x01, 0x00, 1),
+        (0x80, 0x80, 0x00, 1),
+        (0xFF, 0xFF, 0xFE, 1),
+        (0x12, 0x34, 0x46, 0),
+    ];
+    for (a, b, result, carry) in cases.iter() {
+        let v = execute(&[(1, *a), (2, *b)], 0x8124, chipper());
+        assert_eq!((v[1], v[0xF]), (*result, *carry), "{:#04X} + {:#04X}", a, b);
+    }
+
+    // VF as the destination ends up holding the carry
+    assert_eq!(execute(&[(2, 0x20), (0xF, 0x10)], 0x8F24, chipper())[0xF], 0);
+    assert_eq!(execute(&[(2, 0xF0), (0xF, 0x20)], 0x8F24, chipper())[0xF], 1);
+
+    // VF as an operand is read before the carry replaces it
+    let v = execute(&[(1, 0x10), (0xF, 0xF0)], 0x81F4, chipper());
+    assert_eq!((v[1], v[0xF]), (0x00, 1));
+}
+
+#[test]
+fn subtract_sets_vf_unless_it_borrows() {
+    let cases = [(0x05, 0x03, 0x02, 1), (0x05, 0x05, 0x00, 1), (0x03, 0x05, 0xFE, 0)];
+    for (a, b, result, no_borrow) in cases.iter() {
+        // 8XY5 is VX - VY and 8XY7 is VY - VX
+        let v = execute(&[(1, *a), (2, *b)], 0x8125, chipper());
+        assert_eq!((v[1], v[0xF]), (*result, *no_borrow), "{:#04X} - {:#04X}", a, b);
+        let v = execute(&[(1, *b), (2, *a)], 0x8127, chipper());
+        assert_eq!((v[1], v[0xF]), (*result, *no_borrow), "{:#04X} - {:#04X}", a, b);
+    }
+
+    assert_eq!(execute(&[(2, 0x03), (0xF, 0x05)], 0x8F25, chipper())[0xF], 1);
+    assert_eq!(execute(&[(2, 0x07), (0xF, 0x05)], 0x8F25, chipper())[0xF], 0);
+    assert_eq!(execute(&[(2, 0x03), (0xF, 0x05)], 0x8F27, chipper())[0xF], 0);
+    assert_eq!(execute(&[(2, 0x07), (0xF, 0x05)], 0x8F27, chipper())[0xF], 1);
+
+    let v = execute(&[(1, 0x10), (0xF, 0x01)], 0x81F5, chipper());
+    assert_eq!((v[1], v[0xF]), (0x0F, 1));
+    let v = execute(&[(1, 0x01), (0xF, 0x10)], 0x81F7, chipper());
+    assert_eq!((v[1], v[0xF]), (0x0F, 1));
+    let v = execute(&[(1, 0x10), (0xF, 0x01)], 0x81F7, chipper());
+    assert_eq!((v[1], v[0xF]), (0xF1, 0));
+}
+
+#[test]
+fn shifts_set_vf_to_the_bit_shifted_out() {
+    let v = execute(&[(1, 0x05)], 0x8106, chipper());
+    assert_eq!((v[1], v[0xF]), (0x02, 1));
+    let v = execute(&[(1, 0x81)], 0x810E, chipper());
+    assert_eq!((v[1], v[0xF]), (0x02, 1));
+    let v = execute(&[(1, 0x42)], 0x810E, chipper());
+    assert_eq!((v[1], v[0xF]), (0x84, 0));
+
+    // VF as the destination ends up holding the bit shifted out
+    assert_eq!(execute(&[(0xF, 0x04)], 0x8F06, chipper())[0xF], 0);
+    assert_eq!(execute(&[(0xF, 0x40)], 0x8F0E, chipper())[0xF], 0);
+    assert_eq!(execute(&[(0xF, 0x81)], 0x8F0E, chipper())[0xF], 1);
+
+    // Without shift-vx VY is shifted, which may be VF
+    let v = execute(&[(1, 0x00), (0xF, 0x03)], 0x81F6, vip());
+    assert_eq!((v[1], v[0xF]), (0x01, 1));
+    let v = execute(&[(1, 0x00), (0xF, 0x03)], 0x81FE, vip());
+    assert_eq!((v[1], v[0xF]), (0x06, 0));
+}
@@ -0,0 +1,88 @@
+use std::fs;
+
+use chipper::asm::{assemble, assemble_file};
+use chipper::disasm::disassemble;
+use chipper::{Mode, ROM};
+
+#[test]
+fn round_trips_bundled_programs_through_the_disassembler() {
+    for entry in fs::read_dir("programs").unwrap() {
+        let path = entry.unwrap().path();
+        let rom = ROM::new(path.to_str().unwrap()).unwrap();
+
+        let listing = disassemble(&rom.data, Mode::Chip8).to_string();
+        let program = assemble(&listing).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(program, rom.data, "{} didn't round trip", path.display());
+    }
+}
+
+#[test]
+fn resolves_labels_and_constants() {
+    let source = "
+        SPEED = 2
+        ROWS EQU sprite_end - sprite
+        loop:
+            LD I, sprite        ; forward reference
+            DRW V0, v1, ROWS
+            add V0, SPEED
+            ADD V1, -1
+            JP loop
+        sprite: DB 0xFF, 0b10000001
+        sprite_end:
+            DW 0x1234, loop + 2
+    ";
+    assert_eq!(assemble(source).unwrap(), vec![
+        0xA2, 0x0A,
+        0xD0, 0x12,
+        0x70, 0x02,
+        0x71, 0xFF,
+        0x12, 0x00,
+        0xFF, 0x81,
+        0x12, 0x34, 0x02, 0x02,
+    ]);
+}
+
+#[test]
+fn assembles_extended_instructions() {
+    let source = "
+        HIGH
+        LD I, LONG 0xABCD
+        PLANE 3
+        SAVE V1, V4
+        LD HF, V2
+        SHR V3
+    ";
+    assert_eq!(assemble(source).unwrap(), vec![
+        0x00, 0xFF,
+        0xF0, 0x00, 0xAB, 0xCD,
+        0xF3, 0x01,
+        0x51, 0x42,
+        0xF2, 0x30,
+        0x83, 0x36,
+    ]);
+}
+
+#[test]
+fn includes_files_relative_to_the_source() {
+    let dir = std::env::temp_dir().join(format!("chipper-asm-{}", std::process::id()));
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(dir.join("main.asm"), "CALL draw\nINCLUDE \"lib/draw.asm\"\n").unwrap();
+    fs::write(dir.join("lib/draw.asm"), "draw:\n    CLS\n    RET\n").unwrap();
+
+    let program = assemble_file(dir.join("main.asm"));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(program.unwrap(), vec![0x22, 0x02, 0x00, 0xE0, 0x00, 0xEE]);
+}
+
+#[test]
+fn reports_errors_with_their_line() {
+    let error = assemble("CLS\nJP nowhere\n").unwrap_err();
+    assert_eq!((error.line, error.message.as_str()), (2, "undefined label or constant 'nowhere'"));
+
+    assert_eq!(assemble("LD V0, 256").unwrap_err().message, "256 is out of range, expected a byte");
+    assert_eq!(assemble("JP 0x1000").unwrap_err().message, "0x1000 is out of range, expected a value up to 0xFFF");
+    assert_eq!(assemble("FOO V0").unwrap_err().message, "unknown instruction 'FOO'");
+    assert_eq!(assemble("LD K, V0").unwrap_err().message, "invalid operands for LD: K, V0");
+    assert_eq!(assemble("a:\na:").unwrap_err().message, "'a' is already defined");
+    assert_eq!(assemble("A = B\nB = A\nDB A").unwrap_err().line, 3);
+}
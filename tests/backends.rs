@@ -0,0 +1,64 @@
+mod common;
+
+use chipper::display::{HEIGHT, WIDTH};
+use chipper::{Display, Framebuffer, Keyboard, MemoryDisplay, MemoryKeyboard};
+use common::{cpu_with_program, run};
+
+#[test]
+fn keys_are_pressed_and_released() {
+    let mut keyboard = MemoryKeyboard::new();
+    assert!((0..16).all(|key| !keyboard.is_key_pressed(key)));
+
+    keyboard.press(0xA);
+    keyboard.press(0x3);
+    assert!(keyboard.is_key_pressed(0xA));
+    assert!(keyboard.is_key_pressed(0x3));
+
+    keyboard.release(0xA);
+    assert!(!keyboard.is_key_pressed(0xA));
+    assert!(keyboard.is_key_pressed(0x3));
+
+    let mut keys = [false; 16];
+    keys[0xF] = true;
+    keyboard.set_keys(keys);
+    assert!(keyboard.is_key_pressed(0xF));
+    assert!(!keyboard.is_key_pressed(0x3));
+}
+
+#[test]
+fn keys_above_f_are_ignored() {
+    let mut keyboard = MemoryKeyboard::new();
+    keyboard.press(0x15);
+    assert!(!keyboard.is_key_pressed(0x15));
+    assert!((0..16).all(|key| !keyboard.is_key_pressed(key)));
+
+    keyboard.press(0x5);
+    keyboard.release(0x15);
+    assert!(keyboard.is_key_pressed(0x5));
+}
+
+#[test]
+fn display_keeps_the_last_framebuffer() {
+    let mut display = MemoryDisplay::new();
+    assert_eq!(display.buffer(), &Framebuffer::new(WIDTH, HEIGHT));
+    assert_eq!(display.updates(), 0);
+
+    let mut buf = Framebuffer::new(WIDTH, HEIGHT);
+    buf.set_pixel(3, 4, 1);
+    display.update_buffer(&buf);
+    assert_eq!(display.buffer(), &buf);
+    assert_eq!(display.updates(), 1);
+}
+
+#[test]
+fn display_shows_draws_and_clears() {
+    // V0 = 0, I = font digit 0, draw it at V0, V0, clear the screen
+    let cpu = run(cpu_with_program(&[0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x00, 0xE0]), 3);
+    let buffer = cpu.display().buffer();
+    // The top two rows of the digit are F0 and 90
+    assert_eq!(buffer.rows().next().unwrap().take(5).collect::<Vec<_>>(), [1, 1, 1, 1, 0]);
+    assert_eq!(buffer.rows().nth(1).unwrap().take(5).collect::<Vec<_>>(), [1, 0, 0, 1, 0]);
+
+    let cpu = run(cpu, 1);
+    assert!(cpu.display().buffer().is_blank());
+}
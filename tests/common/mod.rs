@@ -0,0 +1,40 @@
+//! Fixtures shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use chipper::{Audio, Display, Keyboard, MemoryDisplay, MemoryKeyboard, Mode, NullAudio, Quirks, CPU, ROM};
+
+/// A CPU running on the in-memory keyboard and display.
+pub type TestCpu<A = NullAudio> = CPU<MemoryKeyboard, MemoryDisplay, A>;
+
+/// A CHIP-8 CPU with `program` loaded.
+pub fn cpu_with_program(program: &[u8]) -> TestCpu {
+    cpu_in_mode(program, Mode::Chip8)
+}
+
+/// A CPU with `program` loaded in `mode`.
+pub fn cpu_in_mode(program: &[u8], mode: Mode) -> TestCpu {
+    cpu_with_audio(program, mode, NullAudio)
+}
+
+/// A CPU with `program` loaded in `mode`, behaving according to `quirks`.
+pub fn cpu_with(program: &[u8], mode: Mode, quirks: Quirks) -> TestCpu {
+    let mut cpu = cpu_in_mode(program, mode);
+    cpu.set_quirks(quirks);
+    cpu
+}
+
+/// A CPU with `program` loaded in `mode`, playing sound through `audio`.
+pub fn cpu_with_audio<A: Audio>(program: &[u8], mode: Mode, audio: A) -> TestCpu<A> {
+    let mut cpu = CPU::with_audio(MemoryKeyboard::new(), MemoryDisplay::new(), audio);
+    cpu.set_mode(mode);
+    cpu.load_rom(ROM::from_bytes(program).unwrap()).unwrap();
+    cpu
+}
+
+/// Executes `steps` instructions, none of which may fail.
+pub fn run<K: Keyboard, D: Display, A: Audio>(mut cpu: CPU<K, D, A>, steps: usize) -> CPU<K, D, A> {
+    for _ in 0..steps {
+        cpu.step().unwrap();
+    }
+    cpu
+}
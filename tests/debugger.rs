@@ -0,0 +1,110 @@
+mod common;
+
+use chipper::debugger::{Debugger, Stop, Watch, CONTINUE_LIMIT};
+use common::cpu_with_program;
+
+// V0 = 5, V1 = 0, I = 0x300, call 0x20C, then halt. The subroutine stores V0 at I and returns.
+const PROGRAM: [u8; 16] = [
+    0x60, 0x05,
+    0x61, 0x00,
+    0xA3, 0x00,
+    0x22, 0x0C,
+    0x00, 0x00,
+    0x12, 0x0A,
+    0xF0, 0x55,
+    0x00, 0xEE,
+];
+
+#[test]
+fn continues_to_breakpoints() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(0x20C);
+
+    assert!(matches!(debugger.resume(&mut cpu), Stop::Breakpoint(0x20C)));
+    assert_eq!(cpu.stack(), &[0x208]);
+
+    // Resuming from the breakpoint carries on until the program jumps to itself
+    assert!(matches!(debugger.resume(&mut cpu), Stop::Waiting(0x20A)));
+}
+
+#[test]
+fn stops_when_watched_values_change() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(Watch::Memory(0x300));
+
+    match debugger.resume(&mut cpu) {
+        Stop::Watchpoint { watch, old, new } => assert_eq!((watch, old, new), (Watch::Memory(0x300), 0, 5)),
+        stop => panic!("unexpected stop: {:?}", stop),
+    }
+    assert_eq!(cpu.pc(), 0x20E);
+
+    debugger.remove_watchpoint(Watch::Memory(0x300));
+    debugger.add_watchpoint("i".parse().unwrap());
+    let mut cpu = cpu_with_program(&PROGRAM);
+    assert!(matches!(debugger.step(&mut cpu, 10), Stop::Watchpoint { watch: Watch::I, old: 0, new: 0x300 }));
+}
+
+#[test]
+fn reports_errors() {
+    let mut cpu = cpu_with_program(&[0x00, 0xEE]);
+
+    assert!(matches!(Debugger::new().resume(&mut cpu), Stop::Error(_)));
+    assert_eq!(cpu.pc(), 0x200);
+}
+
+#[test]
+fn gives_up_continuing_after_the_limit() {
+    // Skip if key 0 is pressed, otherwise poll again
+    let mut cpu = cpu_with_program(&[0xE0, 0x9E, 0x12, 0x00, 0x12, 0x04]);
+    let mut debugger = Debugger::new();
+
+    assert!(matches!(debugger.resume(&mut cpu), Stop::Limit(CONTINUE_LIMIT)));
+    assert_eq!(debugger.execute(&mut cpu, "c 101").unwrap(), "still running after 101 instructions\n>  202  1200  JP 0x200");
+
+    cpu.keyboard_mut().press(0);
+    assert!(matches!(debugger.resume_for(&mut cpu, 10), Stop::Waiting(0x204)));
+}
+
+#[test]
+fn executes_commands() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(debugger.execute(&mut cpu, "step 2").unwrap(), ">  204  A300  LD I, 0x300");
+    // An empty line repeats the last command
+    assert_eq!(debugger.execute(&mut cpu, "").unwrap(), ">  20C  F055  LD [I], V0");
+    assert_eq!(debugger.execute(&mut cpu, "stack").unwrap(), " 0  0x208");
+
+    let registers = debugger.execute(&mut cpu, "regs").unwrap();
+    assert!(registers.starts_with("PC 0x20C  I 0x300  SP 1"));
+    assert!(registers.contains("V0 05  V1 00"));
+
+    assert_eq!(debugger.execute(&mut cpu, "x 200 4").unwrap(), "200  60 05 61 00");
+    assert_eq!(debugger.execute(&mut cpu, "break 20a").unwrap(), "breakpoint at 0x20A");
+    assert_eq!(debugger.execute(&mut cpu, "c").unwrap(), "breakpoint at 0x20A\n>* 20A  120A  JP 0x20A");
+    assert_eq!(debugger.execute(&mut cpu, "press 5").unwrap(), "key 5 pressed");
+    assert!(debugger.execute(&mut cpu, "frobnicate").unwrap().starts_with("error: unknown command"));
+    assert_eq!(debugger.execute(&mut cpu, "quit"), None);
+}
+
+#[test]
+fn dumps_memory_up_to_its_end_for_huge_counts() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(debugger.execute(&mut cpu, "x ffc 18446744073709551615").unwrap(), "FFC  00 00 00 00");
+}
+
+#[test]
+fn runs_a_session() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut output = Vec::new();
+
+    Debugger::new().repl(&mut cpu, "s\nd 200\nq\n".as_bytes(), &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with(">  200  6005  LD V0, 0x05\n(chipper) >  202  6100  LD V1, 0x00\n(chipper)"));
+    assert!(output.contains("   200  6005  LD V0, 0x05\n>  202  6100  LD V1, 0x00\n"));
+}
@@ -0,0 +1,58 @@
+use chipper::disasm::disassemble;
+use chipper::{Instruction, Mode, ROM};
+
+#[test]
+fn separates_code_from_data() {
+    let program = [
+        0xA2, 0x08, // 200: LD I, 0x208
+        0x22, 0x06, // 202: CALL 0x206
+        0x12, 0x04, // 204: JP 0x204
+        0x00, 0xEE, // 206: RET
+        0xFF, 0x81, // 208: sprite data
+    ];
+    let disassembly = disassemble(&program, Mode::Chip8);
+
+    let addresses: Vec<u16> = disassembly.code().keys().copied().collect();
+    assert_eq!(addresses, vec![0x200, 0x202, 0x204, 0x206]);
+    assert_eq!(disassembly.labels().get(&0x204).map(String::as_str), Some("loc_204"));
+    assert_eq!(disassembly.labels().get(&0x206).map(String::as_str), Some("sub_206"));
+
+    let listing = disassembly.to_string();
+    assert!(listing.contains("    CALL sub_206"));
+    assert!(listing.contains("loc_204:\n    JP loc_204"));
+    assert!(listing.contains("    DB 0xFF, 0x81"));
+    assert!(listing.contains("; 208  FF 81"));
+}
+
+#[test]
+fn follows_both_outcomes_of_skips() {
+    let program = [
+        0x30, 0x00, // 200: SE V0, 0x00
+        0x12, 0x08, // 202: JP 0x208
+        0x00, 0xFD, // 204: EXIT, SUPER-CHIP only
+        0x12, 0x06, // 206: never reached
+        0x00, 0xE0, // 208: CLS
+        0x12, 0x0A, // 20A: JP 0x20A
+    ];
+
+    let schip = disassemble(&program, Mode::SuperChip);
+    assert_eq!(schip.code().get(&0x204), Some(&Instruction::Exit));
+    assert!(!schip.code().contains_key(&0x206));
+    assert_eq!(schip.code().get(&0x208), Some(&Instruction::Cls));
+
+    // In CHIP-8 00FD is a machine language call, which carries on with the next instruction
+    let chip8 = disassemble(&program, Mode::Chip8);
+    assert_eq!(chip8.code().get(&0x204), Some(&Instruction::Sys(0x0FD)));
+    assert!(chip8.code().contains_key(&0x206));
+}
+
+#[test]
+fn disassembles_ibm_logo() {
+    let rom = ROM::new("programs/IBM").unwrap();
+    let disassembly = disassemble(&rom.data, Mode::Chip8);
+
+    // 21 instructions ending in a jump to itself, followed by the logo sprites
+    assert_eq!(disassembly.code().len(), 21);
+    assert_eq!(disassembly.code().get(&0x228), Some(&Instruction::Jp(0x228)));
+    assert!(disassembly.to_string().contains("    LD I, 0x22A"));
+}
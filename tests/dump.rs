@@ -0,0 +1,45 @@
+use chipper::{dump, Framebuffer, Palette};
+
+fn framebuffer(width: usize, height: usize, set: &[(usize, usize, u8)]) -> Framebuffer {
+    let mut buf = Framebuffer::new(width, height);
+    for (x, y, value) in set {
+        buf.set_pixel(*x, *y, *value);
+    }
+    buf
+}
+
+#[test]
+fn writes_plain_pbm() {
+    let buf = framebuffer(4, 2, &[(0, 0, 1), (3, 0, 2), (1, 1, 3)]);
+    let mut out = Vec::new();
+    dump::write_pbm(&mut out, &buf).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "P1\n4 2\n1 0 0 1\n0 1 0 0\n");
+}
+
+#[test]
+fn writes_png_chunks_with_known_checksums() {
+    let buf = framebuffer(2, 1, &[(0, 0, 1)]);
+    let mut out = Vec::new();
+    dump::write_png(&mut out, &buf, Palette::new(0x000000, 0xC8C864, 0xFF6600, 0x662200)).unwrap();
+
+    let mut expected = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    // IHDR: 2x1, 8 bit RGB, deflate, adaptive filtering, no interlacing
+    expected.extend_from_slice(&[0, 0, 0, 13]);
+    expected.extend_from_slice(b"IHDR");
+    expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+    expected.extend_from_slice(&[0x7B, 0x40, 0xE8, 0xDD]);
+    // IDAT: a zlib header, one final stored block holding the filter byte and two pixels, and
+    // the Adler-32 of the pixels
+    expected.extend_from_slice(&[0, 0, 0, 18]);
+    expected.extend_from_slice(b"IDAT");
+    expected.extend_from_slice(&[0x78, 0x01, 0x01, 0x07, 0x00, 0xF8, 0xFF]);
+    expected.extend_from_slice(&[0x00, 0xC8, 0xC8, 0x64, 0x00, 0x00, 0x00]);
+    expected.extend_from_slice(&[0x0A, 0x2F, 0x01, 0xF5]);
+    expected.extend_from_slice(&[0x63, 0x6D, 0xDB, 0x05]);
+    // IEND
+    expected.extend_from_slice(&[0, 0, 0, 0]);
+    expected.extend_from_slice(b"IEND");
+    expected.extend_from_slice(&[0xAE, 0x42, 0x60, 0x82]);
+
+    assert_eq!(out, expected);
+}
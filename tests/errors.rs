@@ -0,0 +1,93 @@
+mod common;
+
+use chipper::{ChipError, Mode, CPU, MemoryDisplay, MemoryKeyboard, ROM};
+use chipper::rom::MAX_SIZE;
+use common::{cpu_in_mode, cpu_with_program};
+
+#[test]
+fn rejects_roms_larger_than_memory() {
+    match ROM::from_bytes(&vec![0; MAX_SIZE + 1]) {
+        Err(ChipError::RomTooLarge { size, max }) => assert_eq!((size, max), (MAX_SIZE + 1, MAX_SIZE)),
+        _ => panic!("expected RomTooLarge"),
+    }
+
+    // Fits in XO-CHIP memory but not in the 4 KiB of CHIP-8
+    let rom = ROM::from_bytes(&[0; 4096]).unwrap();
+    let mut cpu = CPU::new(MemoryKeyboard::new(), MemoryDisplay::new());
+    assert!(matches!(cpu.load_rom(rom.clone()), Err(ChipError::RomTooLarge { max: 3584, .. })));
+
+    cpu.set_mode(Mode::XoChip);
+    cpu.load_rom(rom).unwrap();
+}
+
+#[test]
+fn missing_rom_is_an_io_error() {
+    assert!(matches!(ROM::new("does/not/exist.ch8"), Err(ChipError::IoError(_))));
+}
+
+#[test]
+fn unknown_opcode_leaves_pc_at_the_instruction() {
+    // V0 = 1, then 8XY8 which doesn't exist
+    let mut cpu = cpu_with_program(&[0x60, 0x01, 0x80, 0x18]);
+
+    cpu.step().unwrap();
+    match cpu.step() {
+        Err(ChipError::UnknownOpcode { pc, opcode }) => assert_eq!((pc, opcode), (0x202, 0x8018)),
+        _ => panic!("expected UnknownOpcode"),
+    }
+    assert_eq!(cpu.pc(), 0x202);
+}
+
+#[test]
+fn extended_opcodes_are_unknown_in_chip8_mode() {
+    // XO-CHIP 5XY2, store V0-V1
+    let mut cpu = cpu_with_program(&[0x50, 0x12]);
+
+    assert!(matches!(cpu.step(), Err(ChipError::UnknownOpcode { pc: 0x200, opcode: 0x5012 })));
+}
+
+#[test]
+fn return_with_empty_stack_underflows() {
+    let mut cpu = cpu_with_program(&[0x00, 0xEE]);
+
+    assert!(matches!(cpu.step(), Err(ChipError::StackUnderflow { pc: 0x200 })));
+}
+
+#[test]
+fn recursive_call_overflows_the_stack() {
+    // Call itself forever
+    let mut cpu = cpu_with_program(&[0x22, 0x00]);
+
+    for _ in 0..16 {
+        cpu.step().unwrap();
+    }
+    assert!(matches!(cpu.step(), Err(ChipError::StackOverflow { pc: 0x200 })));
+}
+
+#[test]
+fn store_past_end_of_memory_is_out_of_bounds() {
+    // I = 0xFFE, store V0-V3
+    let mut cpu = cpu_with_program(&[0xAF, 0xFE, 0xF3, 0x55]);
+
+    cpu.step().unwrap();
+    assert!(matches!(cpu.step(), Err(ChipError::MemoryOutOfBounds { pc: 0x202, address: 0x1000 })));
+}
+
+#[test]
+fn bcd_past_end_of_memory_fails_with_debug_output() {
+    // I = 0xFFFF, store the BCD of V0
+    let mut cpu = cpu_in_mode(&[0xF0, 0x00, 0xFF, 0xFF, 0xF0, 0x33], Mode::XoChip);
+    cpu.enable_debug();
+
+    cpu.step().unwrap();
+    assert!(matches!(cpu.step(), Err(ChipError::MemoryOutOfBounds { pc: 0x204, address: 0x10000 })));
+}
+
+#[test]
+fn running_off_the_end_of_memory_is_out_of_bounds() {
+    // Jump to the last byte of memory
+    let mut cpu = cpu_with_program(&[0x1F, 0xFF]);
+
+    cpu.step().unwrap();
+    assert!(matches!(cpu.step(), Err(ChipError::MemoryOutOfBounds { pc: 0xFFF, address: 0x1000 })));
+}
@@ -0,0 +1,35 @@
+use chipper::Framebuffer;
+
+#[test]
+fn toggling_reports_collisions_per_plane() {
+    let mut buf = Framebuffer::new(128, 64);
+    assert!(!buf.toggle(70, 3, 0b01));
+    assert_eq!(buf.pixel(70, 3), 1);
+
+    // Drawing to the other plane doesn't erase anything
+    assert!(!buf.toggle(70, 3, 0b10));
+    assert_eq!(buf.pixel(70, 3), 3);
+
+    assert!(buf.toggle(70, 3, 0b11));
+    assert_eq!(buf.pixel(70, 3), 0);
+    assert!(buf.is_blank());
+}
+
+#[test]
+fn scrolls_across_words() {
+    let mut buf = Framebuffer::new(128, 64);
+    buf.set_pixel(62, 0, 0b11);
+    buf.set_pixel(127, 63, 0b01);
+
+    buf.scroll_right(4, 0b01);
+    assert_eq!((buf.pixel(62, 0), buf.pixel(66, 0)), (2, 1));
+    assert_eq!(buf.pixel(127, 63), 0);
+
+    buf.scroll_down(2, 0b11);
+    assert_eq!((buf.pixel(62, 2), buf.pixel(66, 2)), (2, 1));
+    assert_eq!(buf.pixels().filter(|pixel| *pixel != 0).count(), 2);
+
+    buf.scroll_up(10, 0b11);
+    buf.scroll_left(4, 0b10);
+    assert!(buf.is_blank());
+}
@@ -0,0 +1,79 @@
+use chipper::{decode, Instruction, Mode};
+
+/// Instructions the original CHIP-8 opcode table contains, written out nibble by nibble.
+fn is_chip8_opcode(opcode: u16) -> bool {
+    let n = opcode & 0xF;
+    let nn = opcode & 0xFF;
+    match opcode >> 12 {
+        0x0 | 0x1 | 0x2 | 0x3 | 0x4 | 0x6 | 0x7 | 0xA | 0xB | 0xC | 0xD => true,
+        0x5 | 0x9 => n == 0,
+        0x8 => n <= 0x7 || n == 0xE,
+        0xE => nn == 0x9E || nn == 0xA1,
+        0xF => [0x07, 0x0A, 0x15, 0x18, 0x1E, 0x29, 0x33, 0x55, 0x65].contains(&nn),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn every_opcode_round_trips_through_encode() {
+    for opcode in 0..=0xFFFF {
+        assert_eq!(decode(opcode).encode(), opcode, "{:#06X} decoded to {:?}", opcode, decode(opcode));
+    }
+}
+
+#[test]
+fn unknown_opcodes_match_the_opcode_table() {
+    let mut unknown = 0;
+    for opcode in 0..=0xFFFF {
+        let instruction = decode(opcode);
+        if is_chip8_opcode(opcode) {
+            // Extended 00XX instructions replace machine language calls
+            assert!(instruction.mode() == Mode::Chip8 || opcode & 0xF000 == 0,
+                    "{:#06X} decoded to {:?}", opcode, instruction);
+        }
+        match instruction {
+            Instruction::Unknown(_) => {
+                assert!(!is_chip8_opcode(opcode), "{:#06X} is a CHIP-8 instruction", opcode);
+                unknown += 1;
+            }
+            _ if instruction.mode() == Mode::Chip8 => assert!(is_chip8_opcode(opcode), "{:#06X}", opcode),
+            _ => {}
+        }
+    }
+
+    // 65536 opcodes minus the 48048 of CHIP-8 and the extensions outside of 00XX, which are 5XY2,
+    // 5XY3, F000, F002, FN01 and FX30, FX3A, FX75, FX85
+    assert_eq!(unknown, 65536 - 48_048 - 512 - 2 - 16 - 4 * 16);
+}
+
+#[test]
+fn decodes_operands() {
+    assert_eq!(decode(0x00E0), Instruction::Cls);
+    assert_eq!(decode(0x00EE), Instruction::Ret);
+    assert_eq!(decode(0x0123), Instruction::Sys(0x123));
+    assert_eq!(decode(0x1ABC), Instruction::Jp(0xABC));
+    assert_eq!(decode(0x2ABC), Instruction::Call(0xABC));
+    assert_eq!(decode(0x3A12), Instruction::SeVxByte { x: 0xA, nn: 0x12 });
+    assert_eq!(decode(0x7F01), Instruction::AddVxByte { x: 0xF, nn: 0x01 });
+    assert_eq!(decode(0x8AB4), Instruction::AddVxVy { x: 0xA, y: 0xB });
+    assert_eq!(decode(0x8ABE), Instruction::Shl { x: 0xA, y: 0xB });
+    assert_eq!(decode(0xB123), Instruction::JpV0(0x123));
+    assert_eq!(decode(0xD125), Instruction::Drw { x: 1, y: 2, n: 5 });
+    assert_eq!(decode(0xE39E), Instruction::Skp(3));
+    assert_eq!(decode(0xF265), Instruction::LdVxI(2));
+    assert_eq!(decode(0x8AB8), Instruction::Unknown(0x8AB8));
+}
+
+#[test]
+fn extended_instructions_need_their_mode() {
+    assert_eq!(decode(0x00C4), Instruction::Scd(4));
+    assert_eq!(decode(0x00FF).mode(), Mode::SuperChip);
+    assert_eq!(decode(0xF130).mode(), Mode::SuperChip);
+    assert_eq!(decode(0x00D4), Instruction::Scu(4));
+    assert_eq!(decode(0x00D4).mode(), Mode::XoChip);
+    assert_eq!(decode(0x5122), Instruction::SaveRange { x: 1, y: 2 });
+    assert_eq!(decode(0xF201), Instruction::Plane(2));
+    assert_eq!(decode(0xF000).size(), 4);
+    assert_eq!(decode(0xF000).mode(), Mode::XoChip);
+    assert_eq!(decode(0x6000).size(), 2);
+}
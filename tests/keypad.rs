@@ -0,0 +1,119 @@
+mod common;
+
+use chipper::Mode;
+use common::{cpu_in_mode, cpu_with_program, run};
+
+#[test]
+fn ex9e_skips_when_key_is_pressed() {
+    // V1 = 7, skip if key V1 is pressed
+    let mut cpu = cpu_with_program(&[0x61, 0x07, 0xE1, 0x9E]);
+    cpu.keyboard_mut().press(7);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc(), 0x206);
+}
+
+#[test]
+fn ex9e_does_not_skip_when_key_is_released() {
+    let mut cpu = cpu_with_program(&[0x61, 0x07, 0xE1, 0x9E]);
+    cpu.keyboard_mut().press(6);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc(), 0x204);
+}
+
+#[test]
+fn exa1_skips_when_key_is_released() {
+    let mut cpu = cpu_with_program(&[0x61, 0x07, 0xE1, 0xA1]);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc(), 0x206);
+}
+
+#[test]
+fn exa1_does_not_skip_when_key_is_pressed() {
+    let mut cpu = cpu_with_program(&[0x61, 0x07, 0xE1, 0xA1]);
+    cpu.keyboard_mut().press(7);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc(), 0x204);
+}
+
+#[test]
+fn fx0a_blocks_until_a_key_is_pressed_and_released() {
+    let mut cpu = cpu_with_program(&[0xF3, 0x0A]);
+
+    for _ in 0..10 {
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0x200);
+    }
+
+    cpu.keyboard_mut().press(0xB);
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x200, "the key is only stored once released");
+
+    cpu.keyboard_mut().release(0xB);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x202);
+    assert_eq!(cpu.registers()[3], 0xB);
+}
+
+#[test]
+fn fx0a_ignores_other_keys_while_waiting_for_release() {
+    let mut cpu = cpu_with_program(&[0xF3, 0x0A]);
+
+    cpu.keyboard_mut().press(0x2);
+    cpu.step().unwrap();
+    cpu.keyboard_mut().press(0x9);
+    cpu.keyboard_mut().release(0x2);
+    cpu.step().unwrap();
+
+    assert_eq!(cpu.pc(), 0x202);
+    assert_eq!(cpu.registers()[3], 0x2);
+}
+
+#[test]
+fn fx0a_picks_up_a_key_held_before_the_instruction() {
+    let mut cpu = cpu_with_program(&[0xF3, 0x0A]);
+    cpu.keyboard_mut().press(0x0);
+
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x200);
+
+    cpu.keyboard_mut().release(0x0);
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc(), 0x202);
+    assert_eq!(cpu.registers()[3], 0x0);
+}
+
+#[test]
+fn fx0a_waits_at_the_last_address() {
+    for (mode, address) in [(Mode::Chip8, 0xFFE), (Mode::XoChip, 0xFFFE)] {
+        // LD V0, 0 until FX0A at the last address
+        let steps = (address - 0x200) / 2;
+        let mut program = [0x60, 0x00].repeat(steps);
+        program.extend_from_slice(&[0xF3, 0x0A]);
+        let mut cpu = run(cpu_in_mode(&program, mode), steps);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc() as usize, address);
+        cpu.keyboard_mut().press(0x4);
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc() as usize, address);
+
+        // Storing the key moves on, wrapping to the start of memory
+        cpu.keyboard_mut().release(0x4);
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0x000);
+        assert_eq!(cpu.registers()[3], 0x4);
+    }
+}
@@ -0,0 +1,86 @@
+use chipper::movie::{rom_hash, Movie, MovieRecorder, ReplayKeyboard};
+use chipper::{ChipError, CPU, Framebuffer, MemoryDisplay, MemoryKeyboard, Mode, NullAudio, Quirks, Rng, ROM};
+
+// Draws a random sprite at a random position and waits for a key, moving the next sprite to
+// the right by the key's value, forever
+const PROGRAM: [u8; 20] = [
+    0xC1, 0x3F,
+    0xC2, 0x1F,
+    0xC0, 0xFF,
+    0xA3, 0x00,
+    0xF0, 0x55,
+    0xA3, 0x00,
+    0xD1, 0x21,
+    0xF4, 0x0A,
+    0x81, 0x44,
+    0x12, 0x0C,
+];
+
+fn rom() -> ROM {
+    ROM::from_bytes(&PROGRAM).unwrap()
+}
+
+fn record(frames: &[u16], seed: u64) -> (Movie, Framebuffer) {
+    let mut cpu = CPU::with_rng(MovieRecorder::new(MemoryKeyboard::new()), MemoryDisplay::new(), NullAudio, Rng::new(seed));
+    cpu.set_instructions_per_frame(7);
+    cpu.load_rom(rom()).unwrap();
+    let mut movie = Movie::new(&rom(), seed, &cpu);
+
+    for keys in frames {
+        // Keys change between frames, as they would in a window
+        let keyboard = cpu.keyboard_mut().keyboard_mut();
+        for key in 0..16 {
+            if keys & 1 << key != 0 {
+                keyboard.press(key);
+            } else {
+                keyboard.release(key);
+            }
+        }
+        cpu.run_frame().unwrap();
+    }
+    movie.frames = cpu.keyboard().frames().to_vec();
+    (movie, cpu.framebuffer().clone())
+}
+
+fn replay(movie: &Movie) -> CPU<ReplayKeyboard, MemoryDisplay> {
+    let mut cpu = CPU::new(ReplayKeyboard::new(movie), MemoryDisplay::new());
+    movie.configure(&mut cpu);
+    cpu.load_rom(rom()).unwrap();
+    cpu.run_until(usize::MAX, |cpu| cpu.keyboard().is_finished()).unwrap();
+    cpu
+}
+
+#[test]
+fn replays_give_identical_framebuffers() {
+    let frames = [0, 0, 0x0020, 0x0020, 0, 0, 0x0100, 0, 0, 0, 0x8000, 0x8000, 0, 0, 0x0004, 0];
+    let (movie, screen) = record(&frames, 1234);
+    assert_eq!(movie.frames, frames);
+    assert!(!screen.is_blank());
+
+    let cpu = replay(&movie);
+    assert_eq!(cpu.keyboard().frame(), frames.len());
+    assert_eq!(cpu.framebuffer(), &screen);
+
+    // A different seed draws different sprites in different places
+    let mut other = movie.clone();
+    other.seed = 4321;
+    assert_ne!(replay(&other).framebuffer(), &screen);
+}
+
+#[test]
+fn movies_survive_serialization() {
+    let mut movie = record(&[0, 0x0001, 0xFFFF], 7).0;
+    movie.mode = Mode::SuperChip;
+    movie.quirks = Quirks::preset("schip").unwrap();
+
+    let bytes = movie.to_bytes();
+    assert_eq!(&bytes[..5], b"C8MV\x01");
+    assert_eq!(Movie::from_bytes(&bytes).unwrap(), movie);
+    assert!(movie.matches(&rom()));
+    assert!(!movie.matches(&ROM::from_bytes(&PROGRAM[..18]).unwrap()));
+    assert_eq!(movie.rom_hash, rom_hash(&rom()));
+
+    let is_invalid = |bytes: &[u8]| matches!(Movie::from_bytes(bytes), Err(ChipError::InvalidMovie(_)));
+    assert!(is_invalid(&bytes[..bytes.len() - 1]));
+    assert!(is_invalid(b"C8ST\x01"));
+}
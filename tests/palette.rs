@@ -0,0 +1,45 @@
+use chipper::palette::PRESETS;
+use chipper::Palette;
+
+#[test]
+fn parses_presets_and_hex_colours() {
+    for preset in PRESETS.iter() {
+        assert_eq!(preset.parse::<Palette>().unwrap(), Palette::preset(preset).unwrap());
+    }
+    assert_eq!("chipper".parse::<Palette>().unwrap(), Palette::default());
+
+    let palette: Palette = "000000,#FFFFFF".parse().unwrap();
+    assert_eq!(palette, Palette { background: 0, foreground: 0xFFFFFF, ..Palette::default() });
+    assert_eq!("010203,040506,070809,0a0b0c".parse::<Palette>().unwrap(), Palette::new(0x010203, 0x040506, 0x070809, 0x0A0B0C));
+
+    assert!("000000".parse::<Palette>().is_err());
+    assert!("000000,fffff".parse::<Palette>().is_err());
+    assert!("neon".parse::<Palette>().is_err());
+}
+
+#[test]
+fn picks_colours_by_plane() {
+    let palette = Palette::new(1, 2, 3, 4);
+    assert_eq!([0, 1, 2, 3].iter().map(|pixel| palette.color(*pixel)).collect::<Vec<_>>(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn reads_themes() {
+    let theme = "\
+# Amber with a white foreground
+preset = amber
+
+foreground = ffffff
+both-planes=#000001
+";
+    let palette = Palette::from_theme(theme).unwrap();
+    let amber = Palette::preset("amber").unwrap();
+    assert_eq!(palette, Palette { foreground: 0xFFFFFF, both_planes: 1, ..amber });
+    assert_eq!(Palette::from_theme("").unwrap(), Palette::default());
+
+    let error = Palette::from_theme("background = 000000\nforground = ffffff").unwrap_err();
+    assert!(error.to_string().starts_with("line 2: unknown key 'forground'"));
+    assert!(Palette::from_theme("preset = neon").is_err());
+    assert!(Palette::from_theme("background").is_err());
+    assert!(Palette::from_theme("background = black").is_err());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use chipper::quirks::PRESETS;
+use chipper::{Mode, Quirks};
+use common::{cpu_with, run};
+
+#[test]
+fn parses_presets_and_overrides() {
+    assert_eq!("vip".parse(), Ok(Quirks::preset("vip").unwrap()));
+
+    let quirks: Quirks = "schip,no-jump-vx,wrap-sprites".parse().unwrap();
+    assert!(quirks.shift_vx);
+    assert!(!quirks.jump_vx);
+    assert!(quirks.wrap_sprites);
+
+    let quirks: Quirks = "no-shift-vx".parse().unwrap();
+    assert_eq!(quirks, Quirks { shift_vx: false, ..Quirks::default() });
+
+    assert!("vip,bogus".parse::<Quirks>().is_err());
+}
+
+#[test]
+fn quirks_round_trip_through_text() {
+    for preset in PRESETS.iter() {
+        let quirks = Quirks::preset(preset).unwrap();
+        assert_eq!(quirks.to_string().parse::<Quirks>().unwrap(), quirks);
+    }
+    assert_eq!(Quirks::preset("vip").unwrap().to_string(), "no-shift-vx,increment-i,no-jump-vx,no-wrap-sprites,vf-reset,no-i-overflow");
+}
+
+#[test]
+fn shift_uses_vy_unless_shift_vx_is_set() {
+    // V1 = 0x10, V2 = 0x81, V1 = V2 >> 1
+    let program = [0x61, 0x10, 0x62, 0x81, 0x81, 0x26];
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("vip").unwrap()), 3);
+    assert_eq!(cpu.registers()[1], 0x40);
+    assert_eq!(cpu.registers()[0xF], 1);
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("schip").unwrap()), 3);
+    assert_eq!(cpu.registers()[1], 0x08);
+    assert_eq!(cpu.registers()[0xF], 0);
+}
+
+#[test]
+fn jump_with_offset_uses_vx_when_jump_vx_is_set() {
+    // V0 = 4, V3 = 8, jump to 0x300 + offset
+    let program = [0x60, 0x04, 0x63, 0x08, 0xB3, 0x00];
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("vip").unwrap()), 3);
+    assert_eq!(cpu.pc(), 0x304);
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("schip").unwrap()), 3);
+    assert_eq!(cpu.pc(), 0x308);
+}
+
+#[test]
+fn load_store_increments_i_only_with_increment_i() {
+    // I = 0x300, store V0..V2, store V0 again
+    let program = [0xA3, 0x00, 0xF2, 0x55, 0x60, 0x07, 0xF0, 0x55, 0xA3, 0x00, 0xF1, 0x65];
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("vip").unwrap()), 6);
+    assert_eq!(cpu.registers()[0], 0);
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::preset("schip").unwrap()), 6);
+    assert_eq!(cpu.registers()[0], 7);
+}
+
+#[test]
+fn add_to_i_sets_vf_only_with_i_overflow() {
+    // VF = 7, I = 0xFFE, V0 = 1, I += V0, V0 = 2, I += V0
+    let program = [0x6F, 0x07, 0xAF, 0xFE, 0x60, 0x01, 0xF0, 0x1E, 0x60, 0x02, 0xF0, 0x1E];
+
+    let cpu = run(cpu_with(&program, Mode::Chip8, Quirks::default()), 6);
+    assert_eq!(cpu.i(), 0x001);
+    assert_eq!(cpu.registers()[0xF], 7);
+
+    let amiga: Quirks = "i-overflow".parse().unwrap();
+    let cpu = run(cpu_with(&program, Mode::Chip8, amiga), 4);
+    assert_eq!((cpu.i(), cpu.registers()[0xF]), (0xFFF, 0));
+    let cpu = run(cpu_with(&program, Mode::Chip8, amiga), 6);
+    assert_eq!((cpu.i(), cpu.registers()[0xF]), (0x001, 1));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use chipper::rewind::Rewind;
+use chipper::Mode;
+use common::cpu_with_program;
+
+// Counts V0 up, stores it at 0x300 and draws it as a digit, forever
+const PROGRAM: [u8; 14] = [
+    0x70, 0x01,
+    0xA3, 0x00,
+    0xF0, 0x55,
+    0xF0, 0x29,
+    0x00, 0xE0,
+    0xD1, 0x15,
+    0x12, 0x00,
+];
+
+#[test]
+fn steps_back_through_every_recorded_frame() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut rewind = Rewind::new(100);
+    let mut states = vec![cpu.save_state()];
+    rewind.record(&cpu);
+    for _ in 0..20 {
+        cpu.run_frame().unwrap();
+        rewind.record(&cpu);
+        states.push(cpu.save_state());
+    }
+    assert_eq!(rewind.len(), 20);
+
+    states.pop();
+    while let Some(state) = states.pop() {
+        assert!(rewind.step_back(&mut cpu));
+        assert_eq!(cpu.save_state(), state);
+        assert_eq!(cpu.display().buffer(), cpu.framebuffer());
+    }
+    assert!(rewind.is_empty());
+    assert!(!rewind.step_back(&mut cpu));
+
+    // Running again after rewinding records from the rewound state
+    cpu.run_frame().unwrap();
+    rewind.record(&cpu);
+    assert!(rewind.step_back(&mut cpu));
+    assert_eq!(cpu.pc(), 0x200);
+}
+
+#[test]
+fn keeps_only_the_most_recent_frames() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut rewind = Rewind::new(5);
+    rewind.record(&cpu);
+    let mut states = Vec::new();
+    for _ in 0..30 {
+        cpu.run_frame().unwrap();
+        rewind.record(&cpu);
+        states.push(cpu.save_state());
+    }
+    assert_eq!(rewind.len(), 5);
+
+    for _ in 0..5 {
+        assert!(rewind.step_back(&mut cpu));
+    }
+    assert!(!rewind.step_back(&mut cpu));
+    assert_eq!(cpu.save_state(), states[24]);
+}
+
+#[test]
+fn clearing_stops_rewinding_at_the_next_recorded_frame() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut rewind = Rewind::new(100);
+    rewind.record(&cpu);
+    for _ in 0..3 {
+        cpu.run_frame().unwrap();
+        rewind.record(&cpu);
+    }
+    let loaded = cpu.save_state();
+
+    // As after loading a save state
+    rewind.clear();
+    assert!(rewind.is_empty());
+    rewind.record(&cpu);
+    cpu.run_frame().unwrap();
+    rewind.record(&cpu);
+
+    assert!(rewind.step_back(&mut cpu));
+    assert_eq!(cpu.save_state(), loaded);
+    assert!(!rewind.step_back(&mut cpu));
+}
+
+#[test]
+fn frames_are_delta_compressed() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.set_mode(Mode::XoChip);
+    let mut rewind = Rewind::with_seconds(1);
+    assert_eq!(rewind.capacity(), 60);
+
+    rewind.record(&cpu);
+    for _ in 0..60 {
+        cpu.run_frame().unwrap();
+        rewind.record(&cpu);
+    }
+
+    // A full XO-CHIP state is over 64 KiB, all frames together take little more than one
+    let full = cpu.save_state().to_bytes().len();
+    assert!(rewind.memory_usage() < full * 2, "{} bytes used", rewind.memory_usage());
+}
@@ -0,0 +1,54 @@
+use chipper::state::SaveState;
+use chipper::{CPU, MemoryDisplay, MemoryKeyboard, NullAudio, Rng, ROM};
+
+// Fills V0 to VF with random bytes, then halts
+const PROGRAM: [u8; 34] = [
+    0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0xC3, 0xFF,
+    0xC4, 0xFF, 0xC5, 0xFF, 0xC6, 0xFF, 0xC7, 0xFF,
+    0xC8, 0xFF, 0xC9, 0xFF, 0xCA, 0xFF, 0xCB, 0xFF,
+    0xCC, 0xFF, 0xCD, 0xFF, 0xCE, 0xFF, 0xCF, 0xFF,
+    0x12, 0x20,
+];
+
+fn seeded_cpu(seed: u64) -> CPU<MemoryKeyboard, MemoryDisplay> {
+    let mut cpu = CPU::with_rng(MemoryKeyboard::new(), MemoryDisplay::new(), NullAudio, Rng::new(seed));
+    cpu.load_rom(ROM::from_bytes(&PROGRAM).unwrap()).unwrap();
+    cpu
+}
+
+fn random_registers(cpu: &mut CPU<MemoryKeyboard, MemoryDisplay>) -> [u8; 16] {
+    cpu.run_until(100, |cpu| cpu.is_halted()).unwrap();
+    *cpu.registers()
+}
+
+#[test]
+fn same_seed_gives_same_numbers() {
+    let first = random_registers(&mut seeded_cpu(42));
+    assert_eq!(random_registers(&mut seeded_cpu(42)), first);
+    assert_ne!(random_registers(&mut seeded_cpu(43)), first);
+
+    // Sixteen random bytes shouldn't all be the same, even for a seed of 0
+    let zero = random_registers(&mut seeded_cpu(0));
+    assert!(zero.iter().any(|value| *value != zero[0]));
+}
+
+#[test]
+fn generators_continue_from_their_state() {
+    let mut rng = Rng::new(7);
+    rng.next_u64();
+    let mut restored = Rng::from_state(rng.state());
+    for _ in 0..10 {
+        assert_eq!(restored.next_u8(), rng.next_u8());
+    }
+}
+
+#[test]
+fn save_states_include_the_generator() {
+    let mut cpu = seeded_cpu(1);
+    let state = cpu.save_state();
+    let expected = random_registers(&mut cpu);
+
+    let mut other = seeded_cpu(2);
+    other.load_state(&SaveState::from_bytes(&state.to_bytes()).unwrap());
+    assert_eq!(random_registers(&mut other), expected);
+}
@@ -0,0 +1,10 @@
+; Draws the sprite three rows above the bottom edge, the last two rows are clipped or wrap to
+; the top of the screen
+    LD V0, 20
+    LD V1, 29
+    LD I, sprite
+    DRW V0, V1, 5
+done:
+    JP done
+
+INCLUDE "sprite.inc"
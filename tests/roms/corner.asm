@@ -0,0 +1,9 @@
+; Draws the sprite over the bottom right corner, so it's split into four parts when wrapping
+    LD V0, 62
+    LD V1, 30
+    LD I, sprite
+    DRW V0, V1, 5
+done:
+    JP done
+
+INCLUDE "sprite.inc"
@@ -0,0 +1,12 @@
+; Draws a 16x16 SUPER-CHIP sprite over the right edge of the high resolution screen
+    HIGH
+    LD V0, 120
+    LD V1, 20
+    LD I, sprite16
+    DRW V0, V1, 0
+done:
+    JP done
+
+sprite16:
+    DW 0xFFFF, 0x8001, 0x8001, 0x8001, 0x8001, 0x8001, 0x8001, 0xFF81
+    DW 0x8001, 0x8001, 0x8001, 0x8001, 0x8001, 0x8001, 0x8001, 0xFFFF
@@ -0,0 +1,10 @@
+; Draws the sprite four pixels from the right edge, the other half is clipped or wraps to the
+; left edge of the same rows
+    LD V0, 60
+    LD V1, 10
+    LD I, sprite
+    DRW V0, V1, 5
+done:
+    JP done
+
+INCLUDE "sprite.inc"
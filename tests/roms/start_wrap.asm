@@ -0,0 +1,13 @@
+; Starts sprites off screen, which wraps their position onto the screen in every mode: 70, 40
+; becomes 6, 8 and 255, 255 becomes 63, 31, where the rest of the sprite is clipped or wraps
+    LD V0, 70
+    LD V1, 40
+    LD I, sprite
+    DRW V0, V1, 5
+    LD V0, 255
+    LD V1, 255
+    DRW V0, V1, 5
+done:
+    JP done
+
+INCLUDE "sprite.inc"
@@ -0,0 +1,62 @@
+use chipper::scale::{Scaler, Scaling, Viewport};
+use chipper::{Framebuffer, Palette};
+
+const PALETTE: Palette = Palette::new(0, 1, 2, 3);
+
+#[test]
+fn letterboxes_the_screen() {
+    let viewport = |window, scaling| Viewport::new((64, 32), window, scaling);
+    let at = |x, y, width, height| Viewport { x, y, width, height };
+
+    assert_eq!(viewport((640, 320), Scaling::Integer), at(0, 0, 640, 320));
+    assert_eq!(viewport((700, 400), Scaling::Integer), at(30, 40, 640, 320));
+    assert_eq!(viewport((700, 400), Scaling::Fit), at(0, 25, 700, 350));
+    assert_eq!(viewport((700, 300), Scaling::Fit), at(50, 0, 600, 300));
+    // Too small for a whole multiple, so it's scaled down instead
+    assert_eq!(viewport((32, 100), Scaling::Integer), at(0, 42, 32, 16));
+    assert_eq!(Viewport::new((128, 64), (640, 320), Scaling::Integer), at(0, 0, 640, 320));
+}
+
+#[test]
+fn scales_pixels_into_the_viewport() {
+    let mut scaler = Scaler::new(10, 7, Scaling::Integer, PALETTE);
+    let mut buf = Framebuffer::new(4, 2);
+    buf.set_pixel(0, 0, 1);
+    buf.set_pixel(3, 1, 3);
+    assert!(scaler.render(&buf));
+
+    assert_eq!(scaler.viewport(), Viewport { x: 1, y: 1, width: 8, height: 4 });
+    let rows: Vec<&[u32]> = scaler.buffer().chunks(10).collect();
+    assert_eq!(rows[0], &[0; 10]);
+    assert_eq!(rows[1], &[0, 1, 1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(rows[2], rows[1]);
+    assert_eq!(rows[3], &[0, 0, 0, 0, 0, 0, 0, 3, 3, 0]);
+    assert_eq!(rows[4], rows[3]);
+    assert_eq!(rows[6], &[0; 10]);
+}
+
+#[test]
+fn renders_only_when_something_changed() {
+    let mut scaler = Scaler::new(64, 32, Scaling::Fit, PALETTE);
+    let mut buf = Framebuffer::new(64, 32);
+    assert!(scaler.render(&buf));
+    assert!(!scaler.render(&buf));
+
+    buf.toggle(5, 5, 0b01);
+    assert!(scaler.render(&buf));
+    assert_eq!(scaler.buffer()[5 * 64 + 5], 1);
+    assert_eq!(scaler.buffer().iter().sum::<u32>(), 1);
+
+    // A new window size draws everything again, in the new layout
+    scaler.resize(128, 128);
+    assert!(scaler.render(&buf));
+    assert_eq!(scaler.viewport(), Viewport { x: 0, y: 32, width: 128, height: 64 });
+    assert_eq!(scaler.buffer().iter().sum::<u32>(), 4);
+
+    // Switching to the high resolution screen only keeps the new pixels
+    let mut hires = Framebuffer::new(128, 64);
+    hires.toggle(0, 0, 0b10);
+    assert!(scaler.render(&hires));
+    assert_eq!(scaler.buffer()[32 * 128], 2);
+    assert_eq!(scaler.buffer().iter().sum::<u32>(), 2);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use chipper::{Mode, RecordingAudio};
+use common::{cpu_with_audio, run};
+
+#[test]
+fn sound_timer_counts_down_once_per_frame_and_drives_the_buzzer() {
+    // V0 = 3, sound timer = V0, then spin
+    let program = [0x60, 0x03, 0xF0, 0x18, 0x12, 0x04];
+    let mut cpu = cpu_with_audio(&program, Mode::Chip8, RecordingAudio::new());
+
+    cpu.step().unwrap();
+    assert!(!cpu.audio().is_playing());
+
+    cpu.step().unwrap();
+    assert!(cpu.audio().is_playing());
+    assert_eq!(cpu.sound_timer(), 3);
+
+    cpu.run_frame().unwrap();
+    assert_eq!(cpu.sound_timer(), 2);
+
+    cpu.run_frame().unwrap();
+    cpu.run_frame().unwrap();
+    assert_eq!(cpu.sound_timer(), 0);
+    assert_eq!(cpu.audio().changes(), &[true, false]);
+}
+
+#[test]
+fn buzzer_plays_a_square_wave_while_the_sound_timer_runs() {
+    use chipper::audio::Buzzer;
+
+    // V0 = 2, sound timer = V0, then spin
+    let program = [0x60, 0x02, 0xF0, 0x18, 0x12, 0x04];
+    let mut cpu = cpu_with_audio(&program, Mode::Chip8, Buzzer::new(1760));
+    let mut samples = [1.0; 8];
+
+    cpu.audio_mut().fill(&mut samples, 0.5);
+    assert_eq!(samples, [0.0; 8]);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert!(cpu.audio().is_playing());
+    // 440 Hz at 1760 samples per second is two samples high, two low
+    cpu.audio_mut().fill(&mut samples, 0.5);
+    assert_eq!(samples, [0.5, 0.5, -0.5, -0.5, 0.5, 0.5, -0.5, -0.5]);
+
+    cpu.run_frame().unwrap();
+    cpu.run_frame().unwrap();
+    assert!(!cpu.audio().is_playing());
+    cpu.audio_mut().fill(&mut samples, 0.5);
+    assert_eq!(samples, [0.0; 8]);
+}
+
+#[test]
+fn buzzer_plays_xo_chip_audio_patterns() {
+    use chipper::audio::{Audio, Buzzer, DEFAULT_PITCH};
+
+    // I = pattern, load it, V0 = 2, sound timer = V0, then spin
+    let mut program = vec![0xA2, 0x0A, 0xF0, 0x02, 0x60, 0x02, 0xF0, 0x18, 0x12, 0x08];
+    program.extend_from_slice(&[0xCC; 16]);
+    let mut cpu = run(cpu_with_audio(&program, Mode::XoChip, Buzzer::new(4000)), 4);
+
+    // The default pitch plays one pattern bit per sample at 4000 samples per second
+    let mut samples = [0.0; 8];
+    cpu.audio_mut().fill(&mut samples, 1.0);
+    assert_eq!(samples, [1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, -1.0]);
+
+    // Raising the pitch by an octave plays each bit for half a sample
+    cpu.audio_mut().set_pattern(&[0xCC; 16], DEFAULT_PITCH + 48);
+    cpu.audio_mut().fill(&mut samples, 1.0);
+    assert_eq!(samples, [1.0, -1.0, 1.0, -1.0, 1.0, -1.0, 1.0, -1.0]);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use chipper::asm::assemble_file;
+use chipper::{dump, Mode, Quirks};
+use common::cpu_with;
+
+/// The sprite test ROMs in tests/roms, with the mode they run in. Each one draws sprites over
+/// the edges of the screen and halts, the screens expected with and without the `wrap_sprites`
+/// quirk are in tests/roms/expected.
+const ROMS: [(&str, Mode); 5] = [
+    ("right_edge", Mode::Chip8),
+    ("bottom_edge", Mode::Chip8),
+    ("corner", Mode::Chip8),
+    ("start_wrap", Mode::Chip8),
+    ("hires_edge", Mode::SuperChip),
+];
+
+fn screen_after_running(name: &str, mode: Mode, wrap_sprites: bool) -> String {
+    let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    let program = assemble_file(roms.join(format!("{}.asm", name))).unwrap();
+
+    let mut cpu = cpu_with(&program, mode, Quirks { wrap_sprites, ..Quirks::default() });
+    cpu.run_until(100, |cpu| cpu.is_halted()).unwrap();
+    assert!(cpu.is_halted(), "{} didn't halt", name);
+    dump::to_ascii(cpu.framebuffer())
+}
+
+fn expected_screen(name: &str, wrap_sprites: bool) -> String {
+    let mode = if wrap_sprites { "wrap" } else { "clip" };
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/roms/expected/{}.{}.txt", name, mode));
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn sprites_are_clipped_at_the_edges_by_default() {
+    assert!(!Quirks::default().wrap_sprites);
+    for (name, mode) in ROMS.iter() {
+        assert_eq!(screen_after_running(name, *mode, false), expected_screen(name, false), "{}", name);
+    }
+}
+
+#[test]
+fn sprites_wrap_around_with_wrap_sprites() {
+    for (name, mode) in ROMS.iter() {
+        assert_eq!(screen_after_running(name, *mode, true), expected_screen(name, true), "{}", name);
+    }
+}
+
+#[test]
+fn clipped_sprites_dont_bleed_into_the_next_row() {
+    let screen = screen_after_running("right_edge", Mode::Chip8, false);
+    let rows: Vec<&str> = screen.lines().collect();
+    for row in &rows[10..16] {
+        assert!(row.starts_with("........"), "{}", row);
+    }
+    assert_eq!(&rows[10][60..], "####");
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use chipper::state::SaveState;
+use chipper::{ChipError, Mode, Rng};
+use common::cpu_with_program;
+
+// Counts V0 up, draws the digit in V0 and calls a subroutine storing it at 0x300, forever
+const PROGRAM: [u8; 18] = [
+    0x70, 0x01,
+    0xF0, 0x29,
+    0x00, 0xE0,
+    0xD1, 0x15,
+    0x22, 0x0C,
+    0x12, 0x00,
+    0xA3, 0x00,
+    0xF0, 0x55,
+    0x00, 0xEE,
+];
+
+#[test]
+fn loading_a_state_resumes_where_it_was_saved() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.run_until(16, |_| false).unwrap();
+    let state = cpu.save_state();
+    let registers = *cpu.registers();
+    let screen = cpu.framebuffer().clone();
+
+    cpu.run_until(9, |cpu| cpu.pc() == 0x20E).unwrap();
+    assert_eq!(cpu.stack(), &[0x20A]);
+    let later = cpu.save_state();
+
+    cpu.load_state(&state);
+    assert_eq!(cpu.pc(), state.pc());
+    assert_eq!(cpu.registers(), &registers);
+    assert_eq!(cpu.framebuffer(), &screen);
+    assert_eq!(cpu.display().buffer(), &screen);
+    assert_eq!(cpu.save_state(), state);
+
+    // Running from the loaded state gets to the same place again
+    cpu.run_until(9, |cpu| cpu.pc() == 0x20E).unwrap();
+    assert_eq!(cpu.save_state(), later);
+}
+
+#[test]
+fn loading_a_state_restores_the_random_numbers() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.set_rng(Rng::new(1));
+    let bytes = cpu.save_state().to_bytes();
+
+    let mut other = cpu_with_program(&PROGRAM);
+    other.set_rng(Rng::new(2));
+    other.load_state(&SaveState::from_bytes(&bytes).unwrap());
+    assert_eq!(other.rng(), &Rng::new(1));
+}
+
+#[test]
+fn states_survive_serialization() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.set_mode(Mode::XoChip);
+    cpu.run_until(10, |_| false).unwrap();
+    let state = cpu.save_state();
+
+    let bytes = state.to_bytes();
+    assert_eq!(&bytes[..5], b"C8ST\x01");
+    assert_eq!(SaveState::from_bytes(&bytes).unwrap(), state);
+
+    // The mode and the memory size come with the state
+    let mut other = cpu_with_program(&[]);
+    other.load_state(&SaveState::from_bytes(&bytes).unwrap());
+    assert_eq!(other.mode(), Mode::XoChip);
+    assert_eq!(other.memory().len(), 65536);
+    assert_eq!(other.save_state(), state);
+}
+
+#[test]
+fn rejects_invalid_states() {
+    let bytes = cpu_with_program(&PROGRAM).save_state().to_bytes();
+    let is_invalid = |bytes: &[u8]| matches!(SaveState::from_bytes(bytes), Err(ChipError::InvalidSaveState(_)));
+
+    assert!(is_invalid(b"not a save state"));
+    assert!(is_invalid(&bytes[..bytes.len() - 1]));
+
+    let mut newer = bytes.clone();
+    newer[4] = 0xFF;
+    assert!(is_invalid(&newer));
+
+    // The stack pointer comes after the mode, V0-VF, I, PC, the timers and the stack
+    let mut overflowing = bytes.clone();
+    overflowing[6 + 16 + 2 + 2 + 2 + 32] = 17;
+    assert!(is_invalid(&overflowing));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use chipper::Mode;
+use common::{cpu_in_mode, run, TestCpu};
+
+fn lit_pixels(cpu: &TestCpu) -> Vec<(usize, usize)> {
+    let buf = cpu.framebuffer();
+    buf.pixels().enumerate()
+        .filter(|(_, pixel)| *pixel != 0)
+        .map(|(i, _)| (i % buf.width(), i / buf.width()))
+        .collect()
+}
+
+#[test]
+fn switches_resolution_only_in_schip_mode() {
+    let cpu = run(cpu_in_mode(&[0x00, 0xFF], Mode::SuperChip), 1);
+    assert_eq!((cpu.framebuffer().width(), cpu.framebuffer().height()), (128, 64));
+    assert_eq!(cpu.display().buffer().width(), 128);
+
+    let cpu = run(cpu_in_mode(&[0x00, 0xFF, 0x00, 0xFE], Mode::SuperChip), 2);
+    assert_eq!((cpu.framebuffer().width(), cpu.framebuffer().height()), (64, 32));
+
+    let cpu = run(cpu_in_mode(&[0x00, 0xFF], Mode::Chip8), 1);
+    assert_eq!((cpu.framebuffer().width(), cpu.framebuffer().height()), (64, 32));
+}
+
+#[test]
+fn dxy0_draws_a_16x16_sprite() {
+    // hires, I = sprite, draw at (V0, V0) = (0, 0), then spin
+    let mut program = vec![0x00, 0xFF, 0xA2, 0x08, 0xD0, 0x00, 0x12, 0x06];
+    program.extend_from_slice(&[0xFF; 32]);
+
+    let cpu = run(cpu_in_mode(&program, Mode::SuperChip), 3);
+
+    let lit = lit_pixels(&cpu);
+    assert_eq!(lit.len(), 256);
+    assert!(lit.iter().all(|(x, y)| *x < 16 && *y < 16));
+}
+
+#[test]
+fn scrolls_the_screen() {
+    // draw the 8x1 sprite 0x80 at (0, 0), scroll down 3, scroll right 4, scroll left 4, scroll right 4
+    let program = [
+        0xA2, 0x10, 0xD0, 0x01, 0x00, 0xC3, 0x00, 0xFB,
+        0x00, 0xFC, 0x00, 0xFB, 0x12, 0x0C, 0x00, 0x00,
+        0x80,
+    ];
+
+    let cpu = run(cpu_in_mode(&program, Mode::SuperChip), 3);
+    assert_eq!(lit_pixels(&cpu), vec![(0, 3)]);
+
+    let cpu = run(cpu_in_mode(&program, Mode::SuperChip), 6);
+    assert_eq!(lit_pixels(&cpu), vec![(4, 3)]);
+}
+
+#[test]
+fn large_font_and_rpl_flags() {
+    // V0 = 2, V1 = 9, I = large digit V1, store V0..V1 in flags, clear V0 and V1, restore
+    let program = [
+        0x60, 0x02, 0x61, 0x09, 0xF1, 0x30, 0xF1, 0x75,
+        0x60, 0x00, 0x61, 0x00, 0xF1, 0x85,
+    ];
+
+    let cpu = run(cpu_in_mode(&program, Mode::SuperChip), 3);
+    assert_eq!(cpu.i(), 0x50 + 9 * 10);
+
+    let cpu = run(cpu_in_mode(&program, Mode::SuperChip), 7);
+    assert_eq!(cpu.registers()[0], 2);
+    assert_eq!(cpu.registers()[1], 9);
+}
+
+#[test]
+fn exit_halts_the_program() {
+    let mut cpu = run(cpu_in_mode(&[0x00, 0xFD, 0x60, 0x01], Mode::SuperChip), 1);
+    assert!(cpu.has_exited());
+    assert!(cpu.is_halted());
+
+    cpu.run_frame().unwrap();
+    assert_eq!(cpu.registers()[0], 0);
+}
+
+/// A program running `LD V0, 0` until `instruction`, which sits at `address`, and the number of
+/// steps it takes to get there.
+fn program_reaching(address: usize, instruction: [u8; 2]) -> (Vec<u8>, usize) {
+    let steps = (address - 0x200) / 2;
+    let mut program = [0x60, 0x00].repeat(steps);
+    program.extend_from_slice(&instruction);
+    (program, steps)
+}
+
+#[test]
+fn exit_at_the_last_address_stays_there() {
+    for (mode, address) in [(Mode::SuperChip, 0xFFE), (Mode::XoChip, 0xFFFE)] {
+        let (program, steps) = program_reaching(address, [0x00, 0xFD]);
+        let mut cpu = run(cpu_in_mode(&program, mode), steps + 1);
+        assert!(cpu.has_exited());
+        assert_eq!(cpu.pc() as usize, address);
+
+        cpu.run_frame().unwrap();
+        assert_eq!(cpu.pc() as usize, address);
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use chipper::trace::{read_binary, BinaryTrace, TextTrace, Trace, TraceEntry};
+use chipper::Mode;
+use common::cpu_with_program;
+
+/// Keeps the entries where the test can still reach them after handing the sink to the CPU.
+#[derive(Clone, Default)]
+struct SharedTrace(Rc<RefCell<Vec<TraceEntry>>>);
+
+impl Trace for SharedTrace {
+    fn record(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        self.0.borrow_mut().push(entry.clone());
+        Ok(())
+    }
+}
+
+// V0 = 0x12, I = 0x300, DT = V0, then halt
+const PROGRAM: [u8; 8] = [0x60, 0x12, 0xA3, 0x00, 0xF0, 0x15, 0x12, 0x06];
+
+fn entry(pc: u16, opcode: u16, v0: u8, i: u16, delay_timer: u8) -> TraceEntry {
+    let mut v = [0; 16];
+    v[0] = v0;
+    TraceEntry { pc, opcode, v, i, delay_timer, sound_timer: 0, mode: Mode::Chip8, operand: 0 }
+}
+
+#[test]
+fn records_state_before_each_instruction() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let trace = SharedTrace::default();
+    cpu.set_trace(Some(Box::new(trace.clone())));
+    cpu.run_until(4, |_| false).unwrap();
+
+    assert_eq!(*trace.0.borrow(), vec![
+        entry(0x200, 0x6012, 0x00, 0x000, 0x00),
+        entry(0x202, 0xA300, 0x12, 0x000, 0x00),
+        entry(0x204, 0xF015, 0x12, 0x300, 0x00),
+        entry(0x206, 0x1206, 0x12, 0x300, 0x12),
+    ]);
+
+    // Nothing is recorded once tracing is turned off
+    cpu.set_trace(None);
+    cpu.run_until(1, |_| false).unwrap();
+    assert_eq!(trace.0.borrow().len(), 4);
+}
+
+#[test]
+fn writes_text_lines() {
+    let mut out = Vec::new();
+    let mut trace = TextTrace::new(&mut out);
+    trace.record(&entry(0x204, 0xF015, 0x12, 0x300, 0x00)).unwrap();
+    trace.record(&entry(0x206, 0x1206, 0x12, 0x300, 0x12)).unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+0204 F015 12000000000000000000000000000000 0300 00 00 LD DT, V0
+0206 1206 12000000000000000000000000000000 0300 12 00 JP 0x206
+");
+}
+
+#[test]
+fn binary_traces_read_back() {
+    let entries = vec![
+        entry(0x200, 0x6012, 0x00, 0x000, 0x00),
+        entry(0x206, 0x1206, 0x12, 0xFFF, 0x3C),
+    ];
+    let mut out = Vec::new();
+    let mut trace = BinaryTrace::new(&mut out);
+    for entry in &entries {
+        trace.record(entry).unwrap();
+    }
+
+    assert_eq!(out.len(), 5 + 2 * 27);
+    assert_eq!(&out[..5], b"C8TR\x01");
+    assert_eq!(read_binary(&out[..]).unwrap(), entries);
+
+    assert!(read_binary(&out[..out.len() - 1]).is_err());
+    assert!(read_binary(&b"not a trace"[..]).is_err());
+}
+
+#[test]
+fn decodes_in_the_mode_of_the_cpu() {
+    // Switch to high resolution, only an instruction in SUPER-CHIP
+    let text = |mode| {
+        let mut out = Vec::new();
+        TextTrace::new(&mut out).record(&TraceEntry { mode, ..entry(0x200, 0x00FF, 0, 0, 0) }).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert!(text(Mode::Chip8).ends_with(" SYS 0x0FF\n"));
+    assert!(text(Mode::SuperChip).ends_with(" HIGH\n"));
+}
+
+#[test]
+fn records_the_address_of_long_loads() {
+    // I = 0x1234, then halt
+    let mut cpu = cpu_with_program(&[0xF0, 0x00, 0x12, 0x34, 0x12, 0x04]);
+    cpu.set_mode(Mode::XoChip);
+    let trace = SharedTrace::default();
+    cpu.set_trace(Some(Box::new(trace.clone())));
+    cpu.run_until(2, |_| false).unwrap();
+
+    let mut out = Vec::new();
+    let mut text = TextTrace::new(&mut out);
+    for entry in trace.0.borrow().iter() {
+        text.record(entry).unwrap();
+    }
+
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+0200 F000 00000000000000000000000000000000 0000 00 00 LD I, LONG 0x1234
+0204 1204 00000000000000000000000000000000 1234 00 00 JP 0x204
+");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use chipper::{Mode, RecordingAudio};
+use common::{cpu_with_audio, run};
+
+#[test]
+fn long_index_load_reaches_all_of_memory() {
+    let program = [0xF0, 0x00, 0xBE, 0xEF, 0x60, 0x01];
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 2);
+
+    assert_eq!(cpu.i(), 0xBEEF);
+    assert_eq!(cpu.registers()[0], 1);
+}
+
+#[test]
+fn skips_over_long_index_load() {
+    // skip if V0 == 0, which skips the whole F000 NNNN
+    let program = [0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x61, 0x01];
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 2);
+
+    assert_eq!(cpu.i(), 0);
+    assert_eq!(cpu.registers()[1], 1);
+}
+
+#[test]
+fn loads_programs_past_the_classic_memory_size() {
+    // I = 0x1200, load V0 from memory
+    let mut program = vec![0; 0x1001];
+    program[..6].copy_from_slice(&[0xF0, 0x00, 0x12, 0x00, 0xF0, 0x65]);
+    program[0x1000] = 0x2A;
+
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 2);
+
+    assert_eq!(cpu.registers()[0], 0x2A);
+}
+
+#[test]
+fn saves_and_loads_register_ranges_in_both_directions() {
+    let program = [
+        0x61, 0x11, 0x62, 0x22, 0x63, 0x33, 0xA3, 0x00,
+        // store V1..V3 at I, load them back reversed into V6..V4
+        0x51, 0x32, 0x56, 0x43,
+    ];
+
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 6);
+
+    assert_eq!(cpu.i(), 0x300);
+    assert_eq!(&cpu.registers()[4..=6], &[0x33, 0x22, 0x11]);
+}
+
+#[test]
+fn draws_to_the_selected_planes() {
+    // select both planes, I = sprite data, draw a 1 row sprite at (0, 0)
+    let program = [0xF3, 0x01, 0xA2, 0x08, 0xD0, 0x01, 0x12, 0x06, 0xC0, 0x80];
+
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 3);
+
+    let pixels: Vec<u8> = cpu.framebuffer().pixels().take(3).collect();
+    assert_eq!(pixels, [3, 1, 0]);
+    assert_eq!(cpu.registers()[0xF], 0);
+}
+
+#[test]
+fn clears_only_the_selected_planes() {
+    let program = [0xF3, 0x01, 0xA2, 0x0C, 0xD0, 0x01, 0xF2, 0x01, 0x00, 0xE0, 0x12, 0x0A, 0x80, 0x80];
+
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 5);
+
+    assert_eq!(cpu.framebuffer().pixel(0, 0), 1);
+}
+
+#[test]
+fn loads_audio_patterns_and_pitch() {
+    let mut program = vec![0xA2, 0x08, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A];
+    program.extend_from_slice(&[0xAA; 16]);
+
+    let cpu = run(cpu_with_audio(&program, Mode::XoChip, RecordingAudio::new()), 4);
+
+    assert_eq!(cpu.audio().pattern(), Some(([0xAA; 16], 0x70)));
+}